@@ -0,0 +1,42 @@
+// generated by pomme, do not edit
+
+pub const INPUTS: [u32; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+pub const OUTPUTS: [u32; 9] = [17, 18, 19, 20, 21, 22, 23, 24, 32];
+
+/// the states of the outputs from the states of the inputs
+#[allow(unused_variables)]
+pub fn evaluate(inputs: &[bool; 16]) -> [bool; 9] {
+    let n1 = inputs[0];
+    let n2 = inputs[1];
+    let n3 = inputs[2];
+    let n4 = inputs[3];
+    let n5 = inputs[4];
+    let n6 = inputs[5];
+    let n7 = inputs[6];
+    let n8 = inputs[7];
+    let n9 = inputs[8];
+    let n10 = inputs[9];
+    let n11 = inputs[10];
+    let n12 = inputs[11];
+    let n13 = inputs[12];
+    let n14 = inputs[13];
+    let n15 = inputs[14];
+    let n16 = inputs[15];
+    let n17 = matches!(n1 as u8 + n9 as u8, 1);
+    let n25 = matches!(n1 as u8 + n9 as u8, 2);
+    let n18 = matches!(n2 as u8 + n10 as u8 + n25 as u8, 1 | 3);
+    let n26 = matches!(n2 as u8 + n10 as u8 + n25 as u8, 2 | 3);
+    let n19 = matches!(n3 as u8 + n11 as u8 + n26 as u8, 1 | 3);
+    let n27 = matches!(n3 as u8 + n11 as u8 + n26 as u8, 2 | 3);
+    let n20 = matches!(n4 as u8 + n12 as u8 + n27 as u8, 1 | 3);
+    let n28 = matches!(n4 as u8 + n12 as u8 + n27 as u8, 2 | 3);
+    let n21 = matches!(n5 as u8 + n13 as u8 + n28 as u8, 1 | 3);
+    let n29 = matches!(n5 as u8 + n13 as u8 + n28 as u8, 2 | 3);
+    let n22 = matches!(n6 as u8 + n14 as u8 + n29 as u8, 1 | 3);
+    let n30 = matches!(n6 as u8 + n14 as u8 + n29 as u8, 2 | 3);
+    let n23 = matches!(n7 as u8 + n15 as u8 + n30 as u8, 1 | 3);
+    let n31 = matches!(n7 as u8 + n15 as u8 + n30 as u8, 2 | 3);
+    let n24 = matches!(n8 as u8 + n16 as u8 + n31 as u8, 1 | 3);
+    let n32 = false;
+    [n17, n18, n19, n20, n21, n22, n23, n24, n32]
+}
@@ -0,0 +1,343 @@
+use crate::Graph;
+use crate::Node;
+use crate::Trigger;
+use std::collections::HashMap;
+
+type NodeId = u32;
+
+/// the number of input vectors simulated at once, one per bit of a word
+pub const LANES: usize = 64;
+
+#[derive(Debug)]
+struct StoringBlock {
+    index: u32,
+    source: u32,
+    button_node: u32,
+    trigger: Trigger,
+    was_button_on: u64,
+    // an edge-triggered block whose source isn't before its button in the levels samples the
+    // state its source had before the pass, the change of the button reaching it first
+    reads_previous_source: bool,
+    previous_source: u64,
+}
+
+/// a copy of a `Graph` simulating 64 independent input vectors at once, the bit i of the words
+/// being the state of the lane i
+/// the logic blocks are evaluated in topological order with bitwise operations and the storing
+/// blocks are updated until the graph is stable, so the results are the stable states of the
+/// `Graph`: like there, an edge-triggered storing block takes the state its source has when its
+/// button changes, the state from before the changes when the source comes after the button
+/// the inputs are the input nodes, the logic blocks without parents (lamps and rocks) and the
+/// clocks
+#[derive(Debug)]
+pub struct BitParallelGraph {
+    indexes: HashMap<NodeId, u32>,
+    states: Vec<u64>,
+    requirements: Vec<u8>,
+    is_input: Vec<bool>,
+    // the parents of the node i are `parents[parents_offsets[i]..parents_offsets[i + 1]]`
+    parents_offsets: Vec<u32>,
+    parents: Vec<u32>,
+    // the logic blocks which are not inputs, each one after its parents
+    order: Vec<u32>,
+    storing_blocks: Vec<StoringBlock>,
+}
+
+impl From<&Graph> for BitParallelGraph {
+    /// every lane starts with the current state of the graph
+    /// panics if the logic blocks make a loop, if one has more than 7 parents or if the graph has
+    /// forced nodes, memory outputs or random nodes
+    fn from(graph: &Graph) -> Self {
+        assert!(
+            !graph.has_pending_actions(),
+            "can't convert a graph while changes are pending"
+        );
+        assert!(
+            !graph.has_forced_nodes(),
+            "can't convert a graph with forced nodes, they must be released"
+        );
+        let ids = graph.get_node_ids();
+        let indexes = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as u32))
+            .collect::<HashMap<NodeId, u32>>();
+        let levels = graph.get_levels().expect("the logic blocks make a loop");
+        // the level of the logic blocks plus one, the other nodes change before the levels
+        let node_levels = levels
+            .iter()
+            .enumerate()
+            .flat_map(|(i, level)| level.iter().map(move |node_id| (*node_id, i + 1)))
+            .collect::<HashMap<NodeId, usize>>();
+        let get_level = |node_id: &NodeId| node_levels.get(node_id).copied().unwrap_or(0);
+
+        let mut states = Vec::with_capacity(ids.len());
+        let mut requirements = Vec::with_capacity(ids.len());
+        let mut is_input = Vec::with_capacity(ids.len());
+        let mut parents_offsets = Vec::with_capacity(ids.len() + 1);
+        let mut parents = Vec::new();
+        let mut storing_blocks = Vec::new();
+        parents_offsets.push(0);
+        for (index, id) in ids.iter().enumerate() {
+            let node = graph.get_node(*id).unwrap();
+            let node_parents = graph.get_parents(*id);
+            states.push(if node.is_on() { u64::MAX } else { 0 });
+            match node {
+                Node::LogicBlock(node) => {
+                    assert!(
+                        node_parents.len() < 8,
+                        "the logic block {} has too many parents",
+                        id
+                    );
+                    requirements.push(node.get_requirements());
+                    is_input.push(node_parents.is_empty());
+                }
+                Node::StoringBlock(node) => {
+                    requirements.push(0);
+                    is_input.push(false);
+                    storing_blocks.push(StoringBlock {
+                        index: index as u32,
+                        source: indexes[&node.source],
+                        button_node: indexes[&node.button_node],
+                        trigger: node.trigger,
+                        was_button_on: if node.was_button_on { u64::MAX } else { 0 },
+                        reads_previous_source: node.trigger != Trigger::Level
+                            && get_level(&node.source) >= get_level(&node.button_node),
+                        previous_source: 0,
+                    });
+                }
+                Node::Clock(_) | Node::Input(_) => {
+                    requirements.push(0);
+                    is_input.push(true);
+                }
+                Node::Memory(_) => panic!("the memory output {} can't be converted", id),
+                Node::Random(_) => panic!("the random node {} can't be converted", id),
+            }
+            parents.extend(node_parents.iter().map(|parent| indexes[parent]));
+            parents_offsets.push(parents.len() as u32);
+        }
+
+        let order = levels
+            .into_iter()
+            .flatten()
+            .map(|node_id| indexes[&node_id])
+            .collect();
+
+        BitParallelGraph {
+            indexes,
+            states,
+            requirements,
+            is_input,
+            parents_offsets,
+            parents,
+            order,
+            storing_blocks,
+        }
+    }
+}
+
+impl BitParallelGraph {
+    /* getters */
+    /// the state of the node in every lane
+    pub fn get(&self, node_id: NodeId) -> u64 {
+        self.states[self.get_index(node_id)]
+    }
+
+    pub fn is_on(&self, node_id: NodeId, lane: usize) -> bool {
+        assert!(lane < LANES, "there are only {} lanes", LANES);
+        self.get(node_id) >> lane & 1 == 1
+    }
+
+    /* pub methods */
+    /// set the state of an input in every lane, the changes are applied by `evaluate`
+    pub fn set_input(&mut self, node_id: NodeId, lanes: u64) {
+        let index = self.get_index(node_id);
+        assert!(
+            self.is_input[index],
+            "the node {} is not an input, a logic block without parents nor a clock",
+            node_id
+        );
+        self.states[index] = lanes;
+    }
+
+    /// compute the state of every node from the inputs
+    /// panics if the storing blocks never become stable
+    pub fn evaluate(&mut self) {
+        for _ in 0..=self.storing_blocks.len() {
+            for node in &mut self.storing_blocks {
+                node.previous_source = self.states[node.source as usize];
+            }
+            for i in 0..self.order.len() {
+                let index = self.order[i] as usize;
+                self.states[index] = self.evaluate_logic_block(index);
+            }
+            // every storing block samples its source before any of them is written, so the
+            // storing blocks being the source of each other all take the previous state
+            let mut new_states = Vec::with_capacity(self.storing_blocks.len());
+            for node in &mut self.storing_blocks {
+                let button = self.states[node.button_node as usize];
+                let is_sampling = match node.trigger {
+                    Trigger::Level => button,
+                    Trigger::RisingEdge => button & !node.was_button_on,
+                    Trigger::FallingEdge => !button & node.was_button_on,
+                };
+                node.was_button_on = button;
+                let state = self.states[node.index as usize];
+                let source = match node.reads_previous_source {
+                    true => node.previous_source,
+                    false => self.states[node.source as usize],
+                };
+                new_states.push(is_sampling & source | !is_sampling & state);
+            }
+            let mut is_stable = true;
+            for (node, new_state) in self.storing_blocks.iter().zip(new_states) {
+                is_stable &= self.states[node.index as usize] == new_state;
+                self.states[node.index as usize] = new_state;
+            }
+            if is_stable {
+                return;
+            }
+        }
+        panic!("the storing blocks never become stable");
+    }
+
+    /* private methods */
+    fn get_index(&self, node_id: NodeId) -> usize {
+        *self.indexes.get(&node_id).expect("node not found") as usize
+    }
+
+    fn get_parents(&self, index: usize) -> &[u32] {
+        let start = self.parents_offsets[index] as usize;
+        let end = self.parents_offsets[index + 1] as usize;
+        &self.parents[start..end]
+    }
+
+    /// count the parents on in every lane with a 3 bits counter and check the requirements
+    fn evaluate_logic_block(&self, index: usize) -> u64 {
+        let mut count = [0u64; 3];
+        for parent in self.get_parents(index) {
+            let mut carry = self.states[*parent as usize];
+            for bit in &mut count {
+                let sum = *bit ^ carry;
+                carry &= *bit;
+                *bit = sum;
+            }
+        }
+        let requirements = self.requirements[index];
+        let mut state = 0;
+        for value in 0..5 {
+            if requirements >> value & 1 == 0 {
+                continue;
+            }
+            state |= (0..3).fold(u64::MAX, |is_value, bit| {
+                is_value
+                    & match value >> bit & 1 {
+                        1 => count[bit],
+                        _ => !count[bit],
+                    }
+            });
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_exhaustive_adder() {
+        let map = init_map("./components/adder.pc");
+        let mut bit_parallel = BitParallelGraph::from(&map);
+        // every pair of bytes, 64 values of the second input at a time
+        for a in 0..256u32 {
+            for b_start in (0..256u32).step_by(LANES) {
+                for bit in 0..8 {
+                    let a_lanes = if a >> bit & 1 == 1 { u64::MAX } else { 0 };
+                    let b_lanes = (0..LANES as u32)
+                        .filter(|lane| (b_start + lane) >> bit & 1 == 1)
+                        .fold(0, |lanes, lane| lanes | 1 << lane);
+                    bit_parallel.set_input(bit + 1, a_lanes);
+                    bit_parallel.set_input(bit + 9, b_lanes);
+                }
+                bit_parallel.evaluate();
+                for lane in 0..LANES {
+                    let sum = (0..8).fold(0, |sum, bit| {
+                        sum | (bit_parallel.is_on(bit + 17, lane) as u32) << bit
+                    });
+                    assert!(sum == (a + b_start + lane as u32) % 256);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_saver() {
+        let map = init_map("./components/saver.pc");
+        let mut bit_parallel = BitParallelGraph::from(&map);
+        // the lane 0 saves 1, the lane 1 saves 0 and the lane 2 doesn't save
+        bit_parallel.set_input(1, 0b001);
+        bit_parallel.set_input(9, 0b011);
+        bit_parallel.evaluate();
+        assert!(bit_parallel.get(10) & 0b111 == 0b001);
+        bit_parallel.set_input(1, 0b111);
+        bit_parallel.set_input(9, 0b000);
+        bit_parallel.evaluate();
+        assert!(bit_parallel.get(10) & 0b111 == 0b001);
+        assert!(bit_parallel.get(18) & 0b111 == 0b001);
+    }
+
+    #[test]
+    fn test_shift_register() {
+        let map = init_map("./components/shift.pc");
+        let mut bit_parallel = BitParallelGraph::from(&map);
+        // the lane 0 shifts 1, 0, 1 and the lane 1 shifts 0, 1, 1
+        for data in [0b01, 0b10, 0b11] {
+            bit_parallel.set_input(1, data);
+            bit_parallel.evaluate();
+            bit_parallel.set_input(2, u64::MAX);
+            bit_parallel.evaluate();
+            bit_parallel.set_input(2, 0);
+            bit_parallel.evaluate();
+        }
+        assert!(bit_parallel.get(4) & 0b11 == 0b11);
+        assert!(bit_parallel.get(5) & 0b11 == 0b10);
+        assert!(bit_parallel.get(6) & 0b11 == 0b01);
+    }
+
+    #[test]
+    fn test_edge_triggered() {
+        // the node 3 samples its source before the input changes it and the node 6 after
+        let mut map = init_map("./components/edge.pc");
+        let mut bit_parallel = BitParallelGraph::from(&map);
+        for is_on in [true, false, true] {
+            map.set(1, is_on).unwrap();
+            map.apply_changes();
+            bit_parallel.set_input(1, if is_on { u64::MAX } else { 0 });
+            bit_parallel.evaluate();
+            for node_id in map.get_node_ids() {
+                let lanes = if map.is_on(node_id) { u64::MAX } else { 0 };
+                assert!(bit_parallel.get(node_id) == lanes);
+            }
+        }
+        assert!(map.is_on(3) && map.is_on(6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_input_panic() {
+        let map = init_map("./components/adder.pc");
+        let mut bit_parallel = BitParallelGraph::from(&map);
+        bit_parallel.set_input(17, u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "forced nodes")]
+    fn test_forced_nodes() {
+        let mut map = init_map("./components/adder.pc");
+        map.force(25, true);
+        map.apply_changes();
+        let _ = BitParallelGraph::from(&map);
+    }
+}
@@ -0,0 +1,96 @@
+#[derive(Debug)]
+pub struct Clock {
+    pub is_on: bool,
+    pub period: u64, // number of ticks of a full cycle, on during the first half and off during the second
+    pub phase: u64,  // tick of the first rising edge
+    pub children: Vec<u32>,
+}
+
+impl Clock {
+    pub fn new(period: u64, phase: u64, children: Vec<u32>) -> Clock {
+        assert!(
+            period >= 2,
+            "the period of a clock must be at least 2 ticks"
+        );
+        assert!(
+            phase < period,
+            "the phase of a clock must be smaller than its period"
+        );
+        let mut clock = Clock {
+            is_on: false,
+            period,
+            phase,
+            children,
+        };
+        clock.is_on = clock.is_on_at(0);
+        clock
+    }
+
+    /// the state the clock must have at the given tick
+    pub fn is_on_at(&self, time: u64) -> bool {
+        let shift = self.period - self.phase % self.period;
+        (time + shift) % self.period < self.period / 2
+    }
+
+    /// the first tick after `time` at which the clock changes its state
+    pub fn next_edge(&self, time: u64) -> u64 {
+        let rising_edge = self.phase % self.period;
+        let falling_edge = (self.phase + self.period / 2) % self.period;
+        [rising_edge, falling_edge]
+            .iter()
+            .map(|edge| {
+                let start = time + 1;
+                start + (edge + self.period - start % self.period) % self.period
+            })
+            .min()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_on_at_test() {
+        let clock = Clock::new(4, 0, vec![]);
+        assert!(clock.is_on);
+        assert!(clock.is_on_at(1));
+        assert!(!clock.is_on_at(2));
+        assert!(!clock.is_on_at(3));
+        assert!(clock.is_on_at(4));
+
+        let clock = Clock::new(4, 1, vec![]);
+        assert!(!clock.is_on);
+        assert!(clock.is_on_at(1));
+        assert!(clock.is_on_at(2));
+        assert!(!clock.is_on_at(3));
+        assert!(!clock.is_on_at(4));
+        assert!(clock.is_on_at(5));
+    }
+
+    #[test]
+    fn next_edge_test() {
+        let clock = Clock::new(4, 1, vec![]);
+        assert!(clock.next_edge(0) == 1);
+        assert!(clock.next_edge(1) == 3);
+        assert!(clock.next_edge(2) == 3);
+        assert!(clock.next_edge(3) == 5);
+
+        let clock = Clock::new(5, 0, vec![]);
+        assert!(clock.next_edge(0) == 2);
+        assert!(clock.next_edge(2) == 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_test_panic() {
+        Clock::new(1, 0, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "smaller than its period")]
+    fn new_phase_test_panic() {
+        Clock::new(4, 4, vec![]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{init_map, Graph, Node};
+use std::{collections::HashSet, fs, path::PathBuf};
+
+fn get_list(node_ids: &[u32]) -> String {
+    node_ids
+        .iter()
+        .map(|node_id| node_id.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// the expression computing the state of a logic block from the states of its parents
+fn get_logic_block_expression(requirements: u8, parents: &[u32]) -> String {
+    // more than 4 parents on is not allowed
+    let max_value = parents.len().min(4);
+    let values = (0..=max_value)
+        .filter(|value| requirements >> value & 1 == 1)
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>();
+    if values.is_empty() {
+        return "false".to_string();
+    }
+    if values.len() == max_value + 1 && parents.len() <= 4 {
+        return "true".to_string();
+    }
+    let count = parents
+        .iter()
+        .map(|parent| format!("n{} as u8", parent))
+        .collect::<Vec<String>>()
+        .join(" + ");
+    format!("matches!({}, {})", count, values.join(" | "))
+}
+
+/// the source of a rust module computing the outputs of the graph from its inputs without the
+/// event queue, the logic blocks are evaluated once each in topological order
+/// the inputs are the input nodes, the logic blocks without parents, the storing blocks and the
+/// clocks, the outputs are the logic blocks without children, both sorted by node
+/// only the logic blocks outside of the loops are compiled, the ones in a loop or after one are
+/// inputs too and the compiled blocks having one of them as child are outputs, so the caller can
+/// step the loops by giving back their states
+/// panics if the graph has forced nodes, memory outputs or random nodes
+pub fn get_rust_source(graph: &Graph) -> String {
+    let (levels, _) = graph.get_acyclic_levels();
+    let compiled = levels.iter().flatten().copied().collect::<HashSet<u32>>();
+    assert!(
+        !graph.has_forced_nodes(),
+        "can't compile a graph with forced nodes, they must be released"
+    );
+    let node_ids = graph.get_node_ids();
+    for node_id in &node_ids {
+        assert!(
+            !matches!(graph.get_node(*node_id).unwrap(), Node::Memory(_)),
+            "the memory output {} can't be compiled",
+            node_id
+        );
+        assert!(
+            !matches!(graph.get_node(*node_id).unwrap(), Node::Random(_)),
+            "the random node {} can't be compiled",
+            node_id
+        );
+    }
+    let inputs = node_ids
+        .iter()
+        .filter(|node_id| !compiled.contains(node_id))
+        .copied()
+        .collect::<Vec<u32>>();
+    let is_in_loop = |node_id: &u32| {
+        matches!(graph.get_node(*node_id).unwrap(), Node::LogicBlock(_))
+            && !graph.get_parents(*node_id).is_empty()
+            && !compiled.contains(node_id)
+    };
+    let outputs = node_ids
+        .iter()
+        .filter(|node_id| compiled.contains(node_id))
+        .filter(|node_id| {
+            let children = graph.get_node(**node_id).unwrap().get_children();
+            children.is_empty() || children.iter().any(is_in_loop)
+        })
+        .copied()
+        .collect::<Vec<u32>>();
+
+    let mut content = String::new();
+    content.push_str("// generated by pomme, do not edit\n\n");
+    content.push_str(&format!(
+        "pub const INPUTS: [u32; {}] = [{}];\n",
+        inputs.len(),
+        get_list(&inputs)
+    ));
+    content.push_str(&format!(
+        "pub const OUTPUTS: [u32; {}] = [{}];\n\n",
+        outputs.len(),
+        get_list(&outputs)
+    ));
+    content.push_str("/// the states of the outputs from the states of the inputs\n");
+    content.push_str("#[allow(unused_variables)]\n");
+    content.push_str(&format!(
+        "pub fn evaluate(inputs: &[bool; {}]) -> [bool; {}] {{\n",
+        inputs.len(),
+        outputs.len()
+    ));
+    for (i, node_id) in inputs.iter().enumerate() {
+        content.push_str(&format!("    let n{} = inputs[{}];\n", node_id, i));
+    }
+    for node_id in levels.iter().flatten() {
+        let requirements = graph
+            .get_logical_block(*node_id)
+            .unwrap()
+            .get_requirements();
+        let expression = get_logic_block_expression(requirements, graph.get_parents(*node_id));
+        content.push_str(&format!("    let n{} = {};\n", node_id, expression));
+    }
+    let outputs = outputs
+        .iter()
+        .map(|node_id| format!("n{}", node_id))
+        .collect::<Vec<String>>();
+    content.push_str(&format!("    [{}]\n", outputs.join(", ")));
+    content.push_str("}\n");
+    content
+}
+
+/// write the rust module evaluating the .pc file next to it with the .rs extension
+pub fn generate_rust(path: &str) {
+    let graph = init_map(path);
+    let mut path = PathBuf::from(path);
+    path.set_extension("rs");
+    fs::write(&path, get_rust_source(&graph))
+        .unwrap_or_else(|_| panic!("Failed to write the file: {}", path.display()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod adder {
+        include!("../components/adder.rs");
+    }
+
+    #[test]
+    fn test_expression() {
+        assert!(get_logic_block_expression(0b01010, &[1, 9]) == "matches!(n1 as u8 + n9 as u8, 1)");
+        assert!(
+            get_logic_block_expression(0b01100, &[2, 10, 25])
+                == "matches!(n2 as u8 + n10 as u8 + n25 as u8, 2 | 3)"
+        );
+        assert!(get_logic_block_expression(0b11000, &[3]) == "false");
+        assert!(get_logic_block_expression(0b00011, &[3]) == "true");
+    }
+
+    #[test]
+    fn test_adder() {
+        // the module included above is the one generated from adder.pc
+        let source = include_str!("../components/adder.rs");
+        assert!(source == get_rust_source(&init_map("./components/adder.pc")));
+
+        assert!(adder::INPUTS == [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+        assert!(adder::OUTPUTS == [17, 18, 19, 20, 21, 22, 23, 24, 32]);
+        for a in 0..256u32 {
+            for b in 0..256u32 {
+                let mut inputs = [false; 16];
+                for bit in 0..8 {
+                    inputs[bit] = a >> bit & 1 == 1;
+                    inputs[bit + 8] = b >> bit & 1 == 1;
+                }
+                let outputs = adder::evaluate(&inputs);
+                let sum = (0..8).fold(0, |sum, bit| sum | (outputs[bit] as u32) << bit);
+                assert!(sum == (a + b) % 256);
+            }
+        }
+    }
+
+    #[test]
+    fn test_loop() {
+        // the nodes 3 and 4 make a latch, the node 5 comes after it and the node 7 feeds it
+        let source = get_rust_source(&init_map("./components/latch.pc"));
+        assert!(source.contains("pub const INPUTS: [u32; 4] = [1, 3, 4, 5];\n"));
+        assert!(source.contains("pub const OUTPUTS: [u32; 2] = [6, 7];\n"));
+        assert!(source.contains("    let n4 = inputs[2];\n"));
+        assert!(source.contains("    let n7 = matches!(n1 as u8, 1);\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "forced nodes")]
+    fn test_forced_nodes() {
+        let mut map = init_map("./components/adder.pc");
+        map.force(25, true);
+        map.apply_changes();
+        let _ = get_rust_source(&map);
+    }
+}
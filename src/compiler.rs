@@ -5,6 +5,20 @@ use std::{
     path::PathBuf,
 };
 
+enum MemoryDeclaration {
+    Rom {
+        file: String,
+        address: Vec<String>,
+    },
+    Ram {
+        depth: usize,
+        write_enable: String,
+        address: Vec<String>,
+        data_in: Vec<String>,
+        file: Option<String>, // the file of a disk
+    },
+}
+
 enum LinkLineType {
     LinkDeclaration,
     Condition,
@@ -47,6 +61,12 @@ fn increment_node_name(input: &str) -> String {
     chars.iter().collect()
 }
 
+/// the number of the line in the file, from 1, the line being a slice of its contents
+fn get_line_number(contents: &str, line: &str) -> usize {
+    let offset = line.as_ptr() as usize - contents.as_ptr() as usize;
+    contents[..offset].matches('\n').count() + 1
+}
+
 fn get_link_line_type(line: &str) -> LinkLineType {
     if line.starts_with("for") {
         return LinkLineType::Boucle;
@@ -61,7 +81,7 @@ fn from_node_name_to_decimal(value: &str) -> usize {
     let mut sum = 0;
     for (i, letter) in value.chars().enumerate() {
         let ascii_value = letter as usize;
-        let is_lower_case_letter = ascii_value >= 65 && ascii_value <= 90;
+        let is_lower_case_letter = (65..=90).contains(&ascii_value);
         assert!(is_lower_case_letter);
         let value = (ascii_value - 64) * (26usize.pow(i as u32));
         sum += value;
@@ -101,7 +121,7 @@ pub fn apply_variable(node: &str, variables: &mut HashMap<String, String>) -> St
                 .collect::<String>()
                 .split("+")
                 .map(|x| x.trim())
-                .filter(|&x| x != "")
+                .filter(|&x| !x.is_empty())
                 .map(|x| {
                     println!("test: {:#?}, {:#?}", x, x.starts_with('$'));
                     if x.starts_with('$') {
@@ -116,7 +136,7 @@ pub fn apply_variable(node: &str, variables: &mut HashMap<String, String>) -> St
             stack = String::new();
             continue;
         }
-        if stack.len() != 0 || letter == '$' {
+        if !stack.is_empty() || letter == '$' {
             stack.push(letter);
             if variables.contains_key(&stack) && stack.chars().nth(1) != Some('(') {
                 result.push_str(variables.get(&stack).unwrap());
@@ -126,7 +146,7 @@ pub fn apply_variable(node: &str, variables: &mut HashMap<String, String>) -> St
             result.push(letter);
         }
     }
-    assert!(stack.len() == 0, "node: {}, stack: {}", node, stack);
+    assert!(stack.is_empty(), "node: {}, stack: {}", node, stack);
     result
 }
 
@@ -184,11 +204,9 @@ pub fn analyse_links_part(
                 assert!(variable_name.starts_with("$"));
                 for node in nodes {
                     variables.insert(variable_name.to_string(), node);
-                    let lines = lines.iter().map(|&x| x).skip(i + 1).collect::<Vec<&str>>();
+                    let lines = lines.iter().copied().skip(i + 1).collect::<Vec<&str>>();
                     let boucle_indent = lines
-                        .iter()
-                        .skip(i)
-                        .next()
+                        .get(i)
                         .unwrap()
                         .chars()
                         .position(|x| x != ' ')
@@ -207,14 +225,12 @@ pub fn analyse_links_part(
                 }
                 let lines = lines
                     .iter()
-                    .map(|&x| x)
+                    .copied()
                     .skip(i + 1)
                     .filter(|&x| x != ":")
                     .collect::<Vec<&str>>();
                 let condition_indent = lines
-                    .iter()
-                    .skip(i)
-                    .next()
+                    .get(i)
                     .unwrap()
                     .chars()
                     .position(|x| x != ' ')
@@ -230,15 +246,32 @@ pub fn analyse_links_part(
 }
 
 pub fn compile(path: &str) {
-    let allowed_sections = ["inputs", "outputs", "def", "links", "imports"];
+    let allowed_sections = [
+        "inputs",
+        "outputs",
+        "def",
+        "clocks",
+        "randoms",
+        "storing",
+        "roms",
+        "rams",
+        "disks",
+        "displays",
+        "keyboards",
+        "transmitters",
+        "receivers",
+        "links",
+        "imports",
+    ];
 
     // divide the content in sections and the sections in lines also remove the comments and empty lines
-    let contents = fs::read_to_string(path).expect(&format!("Failed to read the file: {}", path));
+    let contents =
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to read the file: {}", path));
     let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
     for section in contents.split("\n\n\n") {
         let lines = section
             .split("\n")
-            .filter(|x| x.len() != 0 && &x.trim()[0..=0] != "#")
+            .filter(|x| !x.is_empty() && &x.trim()[0..=0] != "#")
             .collect::<Vec<&str>>();
         let section_name = &lines[0].trim();
         let section_name = &section_name[..section_name.len() - 1];
@@ -253,8 +286,8 @@ pub fn compile(path: &str) {
         let section = lines
             .iter()
             .skip(1)
-            .filter(|x| x.len() != 0 && &x.trim()[0..=0] != "#")
-            .map(|x| *x)
+            .filter(|x| !x.is_empty() && &x.trim()[0..=0] != "#")
+            .copied()
             .collect();
         sections.insert(section_name, section);
     }
@@ -264,6 +297,9 @@ pub fn compile(path: &str) {
     let mut nodes_queue: Vec<String> = Vec::new();
     let mut nodes_hashmap: HashMap<String, Vec<String>> = HashMap::new();
     let mut nodes_requirements = HashMap::new();
+    let mut nodes_clocks: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut nodes_storing: HashMap<String, (String, String, char)> = HashMap::new();
+    let mut nodes_randoms: HashMap<String, Option<String>> = HashMap::new();
     // TODO: imports
     // inputs
     let input_section = sections.get("inputs").expect("inputs field is missing");
@@ -306,7 +342,7 @@ pub fn compile(path: &str) {
         // if range of nodes
         else {
             let nodes =
-                get_nodes_of_range(line[0].trim(), line[1].trim().split(" ").nth(0).unwrap());
+                get_nodes_of_range(line[0].trim(), line[1].trim().split(" ").next().unwrap());
             for node in nodes {
                 nodes_queue.push(node.clone().to_string());
                 nodes_hashmap.insert(node.clone().to_string(), vec![]);
@@ -335,7 +371,7 @@ pub fn compile(path: &str) {
         // if range of nodes
         else {
             let nodes =
-                get_nodes_of_range(line[0].trim(), line[1].trim().split(" ").nth(0).unwrap());
+                get_nodes_of_range(line[0].trim(), line[1].trim().split(" ").next().unwrap());
             for node in nodes {
                 nodes_queue.push(node.clone().to_string());
                 nodes_hashmap.insert(node.clone().to_string(), vec![]);
@@ -343,36 +379,313 @@ pub fn compile(path: &str) {
             }
         }
     }
+
+    // clocks (name period phase), the phase is optional
+    let clock_section = sections.get("clocks").cloned().unwrap_or_default();
+    for line in clock_section {
+        let i = get_line_number(&contents, line);
+        let (name, period, phase) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            [name, period] => (name, period, "0"),
+            [name, period, phase] => (name, period, phase),
+            _ => panic!("line {i} is not valid:\n{line}\nexpected a name, a period and a phase"),
+        };
+        let (Ok(period), Ok(phase)) = (period.parse::<u64>(), phase.parse::<u64>()) else {
+            panic!(
+                "line {i} is not valid:\n{line}\nexpected the period and the phase to be int (u64)"
+            );
+        };
+        assert!(
+            period >= 2 && phase < period,
+            "line {i} is not valid:\n{line}\nexpected a period of at least 2 and a phase smaller than the period"
+        );
+        nodes_queue.push(name.to_string());
+        nodes_hashmap.insert(name.to_string(), vec![]);
+        nodes_clocks.insert(name.to_string(), (period, phase));
+    }
+    // randoms (name clock), without clock the node draws a new state at each tick, and
+    // the seed of the graph (seed number)
+    let random_section = sections.get("randoms").cloned().unwrap_or_default();
+    let mut seed = None;
+    for line in random_section {
+        let i = get_line_number(&contents, line);
+        let (name, clock) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            ["seed", number] => {
+                let number = number.parse::<u64>().unwrap_or_else(|_| {
+                    panic!("line {i} is not valid:\n{line}\nexpected the seed to be an int (u64)")
+                });
+                seed = Some(number);
+                continue;
+            }
+            [name] => (name, None),
+            [name, clock] => (name, Some(clock.to_string())),
+            _ => panic!("line {i} is not valid:\n{line}\nexpected a name and a clock"),
+        };
+        nodes_queue.push(name.to_string());
+        nodes_hashmap.insert(name.to_string(), vec![]);
+        nodes_randoms.insert(name.to_string(), clock);
+    }
+
+    // storing blocks (name button source), followed by rising or falling for the edge triggered
+    // ones
+    let storing_section = sections.get("storing").cloned().unwrap_or_default();
+    for line in storing_section {
+        let i = get_line_number(&contents, line);
+        let (name, button, source, marker) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            [name, button, source] => (name, button, source, '^'),
+            [name, button, source, "rising"] => (name, button, source, '/'),
+            [name, button, source, "falling"] => (name, button, source, '\\'),
+            _ => panic!("line {i} is not valid:\n{line}\nexpected a name, a button, a source and rising or falling for the edge triggered ones"),
+        };
+        nodes_queue.push(name.to_string());
+        nodes_hashmap.insert(name.to_string(), vec![]);
+        nodes_storing.insert(
+            name.to_string(),
+            (button.to_string(), source.to_string(), marker),
+        );
+    }
+    // roms (data nodes, address nodes and file), the data nodes are the bits of the words and the
+    // address nodes the bits of the address, both the least significant first
+    let rom_section = sections.get("roms").cloned().unwrap_or_default();
+    let mut memories: Vec<MemoryDeclaration> = Vec::new();
+    let mut nodes_memories: HashMap<String, (usize, usize)> = HashMap::new();
+    for line in rom_section {
+        let i = get_line_number(&contents, line);
+        let [first_data, "->", last_data, first_address, "->", last_address, file] =
+            line.split_whitespace().collect::<Vec<&str>>()[..]
+        else {
+            panic!("line {i} is not valid:\n{line}\nexpected the data nodes, the address nodes and the file");
+        };
+        for (bit, node) in get_nodes_of_range(first_data, last_data)
+            .into_iter()
+            .enumerate()
+        {
+            nodes_queue.push(node.clone());
+            nodes_hashmap.insert(node.clone(), vec![]);
+            nodes_memories.insert(node, (memories.len(), bit));
+        }
+        memories.push(MemoryDeclaration::Rom {
+            file: file.to_string(),
+            address: get_nodes_of_range(first_address, last_address),
+        });
+    }
+    // rams (data out nodes, address nodes, data in nodes, write enable node and number of words),
+    // the ranges are the least significant first, the disks are rams followed by their file
+    let ram_section = sections.get("rams").cloned().unwrap_or_default();
+    let disk_section = sections.get("disks").cloned().unwrap_or_default();
+    let ram_lines = ram_section.iter().map(|line| (line, false));
+    for (line, is_disk) in ram_lines.chain(disk_section.iter().map(|line| (line, true))) {
+        let i = get_line_number(&contents, line);
+        let mut parameters = line.split_whitespace().collect::<Vec<&str>>();
+        let file = if is_disk { parameters.pop() } else { None };
+        let [first_out, "->", last_out, first_address, "->", last_address, first_in, "->", last_in, write_enable, depth] =
+            parameters[..]
+        else {
+            panic!("line {i} is not valid:\n{line}\nexpected the data out nodes, the address nodes, the data in nodes, the write enable node and the number of words");
+        };
+        let depth = depth.parse::<usize>().unwrap_or_else(|_| {
+            panic!(
+                "line {i} is not valid:\n{line}\nexpected the number of words to be an int (usize)"
+            )
+        });
+        for (bit, node) in get_nodes_of_range(first_out, last_out)
+            .into_iter()
+            .enumerate()
+        {
+            nodes_queue.push(node.clone());
+            nodes_hashmap.insert(node.clone(), vec![]);
+            nodes_memories.insert(node, (memories.len(), bit));
+        }
+        memories.push(MemoryDeclaration::Ram {
+            depth,
+            write_enable: write_enable.to_string(),
+            address: get_nodes_of_range(first_address, last_address),
+            data_in: get_nodes_of_range(first_in, last_in),
+            file: file.map(|file| file.to_string()),
+        });
+    }
+    // displays (name kind nodes), the nodes being a range or a single node
+    let display_section = sections.get("displays").cloned().unwrap_or_default();
+    let mut displays: Vec<(String, String, Vec<String>)> = Vec::new();
+    for line in display_section {
+        let i = get_line_number(&contents, line);
+        let (name, kind, nodes) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            [name, kind, node] => (name, kind, vec![node.to_string()]),
+            [name, kind, first, "->", last] => (name, kind, get_nodes_of_range(first, last)),
+            _ => panic!("line {i} is not valid:\n{line}\nexpected a name, a kind and the nodes"),
+        };
+        displays.push((name.to_string(), kind.to_string(), nodes));
+    }
+    // keyboards (strobe node and data nodes), the nodes being inputs and the data range the least
+    // significant first
+    let keyboard_section = sections.get("keyboards").cloned().unwrap_or_default();
+    let mut keyboards: Vec<(String, Vec<String>)> = Vec::new();
+    for line in keyboard_section {
+        let i = get_line_number(&contents, line);
+        let [strobe, first, "->", last] = line.split_whitespace().collect::<Vec<&str>>()[..] else {
+            panic!("line {i} is not valid:\n{line}\nexpected the strobe and the data nodes");
+        };
+        keyboards.push((strobe.to_string(), get_nodes_of_range(first, last)));
+    }
+    // transmitters (strobe node, data nodes and file, - being the standard output) and receivers
+    // (strobe node, acknowledge node, data nodes and file), the data ranges are the least
+    // significant first
+    let transmitter_section = sections.get("transmitters").cloned().unwrap_or_default();
+    let mut transmitters: Vec<(String, Vec<String>, String)> = Vec::new();
+    for line in transmitter_section {
+        let i = get_line_number(&contents, line);
+        let [strobe, first, "->", last, file] = line.split_whitespace().collect::<Vec<&str>>()[..]
+        else {
+            panic!(
+                "line {i} is not valid:\n{line}\nexpected the strobe, the data nodes and the file"
+            );
+        };
+        let data = get_nodes_of_range(first, last);
+        transmitters.push((strobe.to_string(), data, file.to_string()));
+    }
+    let receiver_section = sections.get("receivers").cloned().unwrap_or_default();
+    let mut receivers: Vec<(String, String, Vec<String>, String)> = Vec::new();
+    for line in receiver_section {
+        let i = get_line_number(&contents, line);
+        let [strobe, acknowledge, first, "->", last, file] =
+            line.split_whitespace().collect::<Vec<&str>>()[..]
+        else {
+            panic!("line {i} is not valid:\n{line}\nexpected the strobe, the acknowledge node, the data nodes and the file");
+        };
+        let data = get_nodes_of_range(first, last);
+        receivers.push((
+            strobe.to_string(),
+            acknowledge.to_string(),
+            data,
+            file.to_string(),
+        ));
+    }
     // TODO: links
     let links_section = sections.get("links").expect("links field is missing");
     let links = analyse_links_part(links_section, &mut HashMap::new(), 0);
     for link in links {
         nodes_hashmap.entry(link.0).and_modify(|x| x.push(link.1));
     }
+    // the storing blocks are updated when their button or source changes and the random nodes
+    // when their clock changes
+    for node in &nodes_queue {
+        let parents = match (nodes_storing.get(node), nodes_randoms.get(node)) {
+            (Some((button, source, _)), _) => vec![button, source],
+            (None, Some(Some(clock))) => vec![clock],
+            _ => continue,
+        };
+        for parent in parents {
+            let children = nodes_hashmap.get_mut(parent).unwrap();
+            if !children.contains(node) {
+                children.push(node.clone());
+            }
+        }
+    }
 
     //println!("{:#?}", nodes_queue);
     //println!("{:#?}", nodes_hashmap);
     //println!("{:#?}", nodes_requirements);
     // TODO: write the nodes expression in the new file
     let mut content = String::new();
+    let get_index = |node: &String| nodes_queue.iter().position(|el| el == node).unwrap() + 1;
+    let get_indexes = |nodes: &Vec<String>, separator: &str| {
+        nodes
+            .iter()
+            .map(|x| get_index(x).to_string())
+            .collect::<Vec<String>>()
+            .join(separator)
+    };
+    if let Some(seed) = seed {
+        content.push_str(&format!("seed {}\n", seed));
+    }
+    for (i, memory) in memories.iter().enumerate() {
+        let line = match memory {
+            MemoryDeclaration::Rom { file, address } => {
+                format!("${} {} {}\n", i, file, get_indexes(address, " "))
+            }
+            MemoryDeclaration::Ram {
+                depth,
+                write_enable,
+                address,
+                data_in,
+                file,
+            } => format!(
+                "{} {} {} {} {}\n",
+                match file {
+                    Some(file) => format!("&{} {}", i, file),
+                    None => format!("%{}", i),
+                },
+                depth,
+                get_index(write_enable),
+                get_indexes(address, ","),
+                get_indexes(data_in, ",")
+            ),
+        };
+        content.push_str(&line);
+    }
     for (i, node) in nodes_queue.iter().enumerate() {
         let index = (i + 1).to_string();
+        let links = nodes_hashmap
+            .get(node)
+            .unwrap()
+            .iter()
+            .map(|x| (nodes_queue.iter().position(|el| el == x).unwrap() + 1).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        if let Some((period, phase)) = nodes_clocks.get(node) {
+            let line = format!("~{} {} {} {}\n", index, period, phase, links);
+            content.push_str(&line);
+            continue;
+        }
+        if let Some(clock) = nodes_randoms.get(node) {
+            let clock = clock
+                .as_ref()
+                .map_or("-".to_string(), |x| get_index(x).to_string());
+            let line = format!("+{} {} {}\n", index, clock, links);
+            content.push_str(&line);
+            continue;
+        }
+        if let Some((memory, bit)) = nodes_memories.get(node) {
+            let line = format!("#{} {} {} {}\n", index, memory, bit, links);
+            content.push_str(&line);
+            continue;
+        }
+        if let Some((button, source, marker)) = nodes_storing.get(node) {
+            let button = nodes_queue.iter().position(|el| el == button).unwrap() + 1;
+            let source = nodes_queue.iter().position(|el| el == source).unwrap() + 1;
+            let line = format!("{}{} {} {} {}\n", marker, index, button, source, links);
+            content.push_str(&line);
+            continue;
+        }
         let requirements = nodes_requirements.get(node).unwrap();
         let requirements = (0..5)
             .map(|x| if requirements & 1 << x > 0 { "1" } else { "0" })
             .rev()
             .collect::<Vec<&str>>()
             .join("");
-        let links = nodes_hashmap
-            .get(node)
-            .unwrap()
+        let line = format!("{} {} {}\n", index, requirements, links);
+        content.push_str(&line);
+    }
+    for (name, kind, nodes) in displays {
+        let nodes = nodes
             .iter()
             .map(|x| (nodes_queue.iter().position(|el| el == x).unwrap() + 1).to_string())
             .collect::<Vec<String>>()
             .join(" ");
-        let line = format!("{} {} {}\n", index, requirements, links);
+        content.push_str(&format!("*{} {} {}\n", name, kind, nodes));
+    }
+    for (strobe, data) in keyboards {
+        let line = format!("?{} {}\n", get_index(&strobe), get_indexes(&data, " "));
         content.push_str(&line);
     }
+    for (strobe, data, file) in transmitters {
+        let (strobe, data) = (get_index(&strobe), get_indexes(&data, " "));
+        content.push_str(&format!("!{} {} {}\n", file, strobe, data));
+    }
+    for (strobe, acknowledge, data, file) in receivers {
+        let (strobe, acknowledge) = (get_index(&strobe), get_index(&acknowledge));
+        let data = get_indexes(&data, " ");
+        content.push_str(&format!("<{} {} {} {}\n", file, strobe, acknowledge, data));
+    }
     let mut path = PathBuf::from(path);
     path.set_extension("pc");
     let mut file = File::create(path.to_str().unwrap()).unwrap();
@@ -476,4 +789,265 @@ mod tests {
         assert!(!map.get_node(23).unwrap().is_on());
         assert!(map.get_node(24).unwrap().is_on());
     }
+
+    #[test]
+    fn test_clock() {
+        compile("./components/clock.pomme");
+        let mut map = init_map("./components/clock.pc");
+        assert!(map.get_clock(4).unwrap().period == 4);
+        // the output stays off until enabled
+        assert!(!map.get_node(2).unwrap().is_on());
+        assert!(!map.get_node(3).unwrap().is_on());
+        map.turn_on_lamp(1);
+        map.apply_changes();
+        assert!(map.get_node(2).unwrap().is_on());
+        map.run_for(2);
+        assert!(!map.get_node(2).unwrap().is_on());
+        assert!(map.get_node(3).unwrap().is_on());
+        map.run_for(1);
+        assert!(!map.get_node(2).unwrap().is_on());
+        map.run_for(1);
+        assert!(map.get_node(2).unwrap().is_on());
+        assert!(!map.get_node(3).unwrap().is_on());
+    }
+
+    /// compile a copy of the component with the text replaced, in the temporary directory
+    fn compile_replaced(directory: &str, component: &str, from: &str, to: &str) {
+        let directory = std::env::temp_dir().join(directory);
+        fs::create_dir_all(&directory).unwrap();
+        let contents = fs::read_to_string(format!("./components/{component}"))
+            .unwrap()
+            .replace(from, to);
+        let path = directory.join(component);
+        fs::write(&path, contents).unwrap();
+        compile(path.to_str().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "line 19 is not valid")]
+    fn test_clock_phase() {
+        compile_replaced("pomme_clock_phase", "clock.pomme", "CK 4 0", "CK 4 4");
+    }
+
+    #[test]
+    #[should_panic(expected = "line 21 is not valid")]
+    fn test_storing_trigger() {
+        compile_replaced(
+            "pomme_storing_trigger",
+            "counter.pomme",
+            "SA AA DA rising",
+            "SA AA DA up",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "line 27 is not valid")]
+    fn test_display_line() {
+        compile_replaced("pomme_display_line", "counter.pomme", "count leds", "count");
+    }
+
+    #[test]
+    fn test_counter() {
+        compile("./components/counter.pomme");
+        let mut map = init_map("./components/counter.pc");
+        assert!(matches!(
+            map.get_storing_block(6).unwrap().trigger,
+            crate::Trigger::RisingEdge
+        ));
+        assert!(map.get_displays()[0].nodes == [2, 3]);
+        let get_count = |map: &crate::Graph| {
+            map.get_node(2).unwrap().is_on() as u8 | (map.get_node(3).unwrap().is_on() as u8) << 1
+        };
+        assert!(get_count(&map) == 0);
+        for count in 1..=5 {
+            // the counter only counts the rising edges of the clock input
+            map.turn_on_lamp(1);
+            map.apply_changes();
+            assert!(get_count(&map) == count % 4);
+            map.turn_off_lamp(1);
+            map.apply_changes();
+            assert!(get_count(&map) == count % 4);
+        }
+    }
+
+    #[test]
+    fn test_keyboard() {
+        compile("./components/typewriter.pomme");
+        let mut map = init_map("./components/typewriter.pc");
+        let keyboard = &map.get_keyboards()[0];
+        assert!(keyboard.strobe == 9 && keyboard.data == (1..=8).collect::<Vec<u32>>());
+        map.get_mut_keyboard(0).unwrap().push(b"k");
+        map.update_keyboards();
+        let key = (10..18).fold(0, |key, node_id| {
+            key | (map.is_on(node_id) as u8) << (node_id - 10)
+        });
+        assert!(key == b'k');
+    }
+
+    #[test]
+    #[should_panic(expected = "line 31 is not valid")]
+    fn test_keyboard_line() {
+        compile_replaced(
+            "pomme_keyboard_line",
+            "typewriter.pomme",
+            "KS KA -> KH",
+            "KA -> KH",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "line 24 is not valid")]
+    fn test_transmitter_line() {
+        compile_replaced(
+            "pomme_transmitter_line",
+            "echo.pomme",
+            "AA DA -> DH -",
+            "AA DA -> DH",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "line 19 is not valid")]
+    fn test_receiver_line() {
+        compile_replaced("pomme_receiver_line", "echo.pomme", "DS AA DA", "DS DA");
+    }
+
+    #[test]
+    fn test_echo() {
+        // echo.pomme writing to a file instead of the standard output
+        let directory = std::env::temp_dir().join("pomme_compiled_echo");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let contents = fs::read_to_string("./components/echo.pomme")
+            .unwrap()
+            .replace("DH -", "DH echo.out");
+        fs::write(directory.join("echo.pomme"), contents).unwrap();
+        fs::copy("./components/echo.txt", directory.join("echo.txt")).unwrap();
+        compile(directory.join("echo.pomme").to_str().unwrap());
+        let mut map = init_map(directory.join("echo.pc").to_str().unwrap());
+        let receiver = &map.get_keyboards()[0];
+        assert!(receiver.strobe == 9 && receiver.acknowledge == Some(10));
+        assert!(map.get_transmitters()[0].strobe == 10);
+        for _ in 0..100 {
+            map.update_keyboards();
+        }
+        let sent = fs::read(directory.join("echo.out")).unwrap();
+        assert!(sent == fs::read("./components/echo.txt").unwrap());
+    }
+
+    #[test]
+    fn test_random() {
+        compile("./components/coin.pomme");
+        let mut map = init_map("./components/coin.pc");
+        assert!(map.get_seed() == 42);
+        assert!(map.get_parents(4) == [3]);
+        map.turn_on_lamp(1);
+        map.apply_changes();
+        let flips = (0..32)
+            .map(|_| {
+                map.run_for(2);
+                assert!(map.is_on(2) == map.is_on(4));
+                map.is_on(2)
+            })
+            .collect::<Vec<bool>>();
+        assert!(flips.contains(&true) && flips.contains(&false));
+    }
+
+    #[test]
+    #[should_panic(expected = "line 21 is not valid")]
+    fn test_random_seed() {
+        compile_replaced("pomme_random_seed", "coin.pomme", "seed 42", "seed -1");
+    }
+
+    #[test]
+    #[should_panic(expected = "line 17 is not valid")]
+    fn test_rom_line() {
+        compile_replaced(
+            "pomme_rom_line",
+            "squares.pomme",
+            "AA -> AB rom.hex",
+            "AA -> AB",
+        );
+    }
+
+    #[test]
+    fn test_rom() {
+        compile("./components/squares.pomme");
+        let mut map = init_map("./components/squares.pc");
+        assert!(map.get_memory(0).unwrap().address == [1, 2]);
+        for address in 0..4 {
+            map.set(1, address & 1 == 1).unwrap();
+            map.set(2, address & 2 == 2).unwrap();
+            map.apply_changes();
+            let word = (3..=6).fold(0, |word, node_id| {
+                word | (map.is_on(node_id) as u32) << (node_id - 3)
+            });
+            assert!(word == address * address);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "line 21 is not valid")]
+    fn test_ram_depth() {
+        compile_replaced("pomme_ram_depth", "registers.pomme", "WA 4", "WA four");
+    }
+
+    #[test]
+    fn test_ram() {
+        compile("./components/registers.pomme");
+        let mut map = init_map("./components/registers.pc");
+        let memory = map.get_memory(0).unwrap();
+        assert!(memory.words.len() == 4);
+        assert!(memory.address == [1, 2] && memory.data_in == [3, 4]);
+        assert!(memory.write_enable == Some(5));
+        for (address, word) in [(3, 2), (0, 1), (3, 3)] {
+            for (node_id, is_on) in [
+                (1, address & 1 == 1),
+                (2, address & 2 == 2),
+                (3, word & 1 == 1),
+                (4, word & 2 == 2),
+                (5, true),
+                (5, false),
+            ] {
+                map.set(node_id, is_on).unwrap();
+                map.apply_changes();
+            }
+            assert!(map.is_on(6) == (word & 1 == 1) && map.is_on(7) == (word & 2 == 2));
+        }
+        assert!(map.get_memory(0).unwrap().words == [1, 0, 0, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "line 21 is not valid")]
+    fn test_disk_file() {
+        // a disk line without its file
+        compile_replaced("pomme_disk_file", "registers.pomme", "rams:", "disks:");
+    }
+
+    #[test]
+    fn test_disk() {
+        // the registers of registers.pomme kept in a file
+        let directory = std::env::temp_dir().join("pomme_compiled_disk");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let contents = fs::read_to_string("./components/registers.pomme")
+            .unwrap()
+            .replace("rams:", "disks:")
+            .replace("WA 4", "WA 4 registers.bin");
+        fs::write(directory.join("registers.pomme"), contents).unwrap();
+        compile(directory.join("registers.pomme").to_str().unwrap());
+        let path = directory.join("registers.pc");
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("&0 registers.bin 4 5 1,2 3,4\n"));
+
+        let mut map = init_map(path.to_str().unwrap());
+        for (node_id, is_on) in [(2, true), (3, true), (5, true)] {
+            map.set(node_id, is_on).unwrap();
+            map.apply_changes();
+        }
+        drop(map);
+        let map = init_map(path.to_str().unwrap());
+        assert!(map.get_memory(0).unwrap().words == [0, 0, 1, 0]);
+    }
 }
@@ -0,0 +1,462 @@
+use crate::Error;
+use crate::Graph;
+use crate::Node;
+use crate::Trigger;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+type NodeId = u32;
+
+/// a node of the dense graph, the nodes it refers to are indexes and not ids
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum DenseNode {
+    // same layout as the data of a `LogicBlock`, the value on the 3 high bits and the requirements
+    // on the 5 low bits
+    LogicBlock(u8),
+    StoringBlock {
+        is_on: bool,
+        source: u32,
+        button_node: u32,
+        trigger: Trigger,
+        was_button_on: bool,
+        sampled_value: Option<bool>,
+    },
+    Clock(bool),
+    Input(bool),
+}
+
+impl DenseNode {
+    fn is_on(&self) -> bool {
+        match self {
+            DenseNode::LogicBlock(data) => 1 << (data >> 5) & data & 0b11111 != 0,
+            DenseNode::StoringBlock { is_on, .. } => *is_on,
+            DenseNode::Clock(is_on) => *is_on,
+            DenseNode::Input(is_on) => *is_on,
+        }
+    }
+}
+
+/// a frozen copy of a `Graph` made to propagate the changes of very big graphs as fast as possible
+/// the nodes are stored contiguously and indexed from 0, their children are stored in a single
+/// array (compressed sparse rows) so applying a change doesn't allocate anything
+/// the nodes are still refered to by their ids in the public methods
+/// the changes are applied in the same order as the zero delay mode of the `Graph`
+/// the `Graph` itself keeps its nodes in a vector indexed by their id, but each node owns its list
+/// of children so it can be edited and simulated in every mode, only its logic blocks, storing
+/// blocks, clocks and inputs in zero delay mode are copied here and the graphs using anything else
+/// are refused when converted
+#[derive(Debug)]
+pub struct DenseGraph {
+    pub(crate) ids: Vec<NodeId>,
+    pub(crate) indexes: HashMap<NodeId, u32>,
+    pub(crate) nodes: Vec<DenseNode>,
+    // the children of the node i are `children[children_offsets[i]..children_offsets[i + 1]]`
+    pub(crate) children_offsets: Vec<u32>,
+    pub(crate) children: Vec<u32>,
+    // (is an increase, node index)
+    actions_queue: VecDeque<(bool, u32)>,
+}
+
+impl From<&Graph> for DenseGraph {
+    /// the graph must be initialised and must not have pending changes, forced nodes, memory
+    /// outputs nor random nodes
+    fn from(graph: &Graph) -> Self {
+        assert!(
+            !graph.has_pending_actions(),
+            "can't convert a graph while changes are pending"
+        );
+        assert!(
+            !graph.has_forced_nodes(),
+            "can't convert a graph with forced nodes, they must be released"
+        );
+        let ids = graph.get_node_ids();
+        let indexes = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as u32))
+            .collect::<HashMap<NodeId, u32>>();
+
+        let mut nodes = Vec::with_capacity(ids.len());
+        let mut children_offsets = Vec::with_capacity(ids.len() + 1);
+        let mut children = Vec::new();
+        children_offsets.push(0);
+        for id in &ids {
+            let node = graph.get_node(*id).unwrap();
+            nodes.push(match node {
+                Node::LogicBlock(node) => {
+                    DenseNode::LogicBlock(node.get_value() << 5 | node.get_requirements())
+                }
+                Node::StoringBlock(node) => DenseNode::StoringBlock {
+                    is_on: node.is_on,
+                    source: indexes[&node.source],
+                    button_node: indexes[&node.button_node],
+                    trigger: node.trigger,
+                    was_button_on: node.was_button_on,
+                    sampled_value: node.sampled_value,
+                },
+                Node::Clock(node) => DenseNode::Clock(node.is_on),
+                Node::Input(node) => DenseNode::Input(node.is_on),
+                Node::Memory(_) => panic!("the memory output {} can't be converted", id),
+                Node::Random(_) => panic!("the random node {} can't be converted", id),
+            });
+            children.extend(node.get_children().iter().map(|child| indexes[child]));
+            children_offsets.push(children.len() as u32);
+        }
+
+        DenseGraph {
+            ids,
+            indexes,
+            nodes,
+            children_offsets,
+            children,
+            actions_queue: VecDeque::new(),
+        }
+    }
+}
+
+impl DenseGraph {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn is_on(&self, node_id: NodeId) -> bool {
+        self.nodes[self.get_index(node_id)].is_on()
+    }
+
+    /// the value of the logic block, the number of its parents that are on
+    pub fn get_value(&self, node_id: NodeId) -> Option<u8> {
+        match self.nodes[self.get_index(node_id)] {
+            DenseNode::LogicBlock(data) => Some(data >> 5),
+            _ => None,
+        }
+    }
+
+    pub fn turn_on_lamp(&mut self, node_id: NodeId) {
+        let index = self.get_index(node_id);
+        let DenseNode::LogicBlock(data) = &mut self.nodes[index] else {
+            panic!("can't turn on a non logic block ({})", node_id);
+        };
+        assert!(
+            *data & 0b11111 == 0b00000,
+            "it is not allowed to turn on a none rock block ({})",
+            node_id
+        );
+        *data |= 0b11111;
+        self.propagate_change(index, true);
+    }
+
+    pub fn turn_off_lamp(&mut self, node_id: NodeId) {
+        let index = self.get_index(node_id);
+        let DenseNode::LogicBlock(data) = &mut self.nodes[index] else {
+            panic!("can't turn off a non logic block ({})", node_id);
+        };
+        assert!(
+            *data & 0b11111 == 0b11111,
+            "it is not allowed to turn off a none lamp block ({})",
+            node_id
+        );
+        *data &= !0b11111;
+        self.propagate_change(index, false);
+    }
+
+    /// set the state of an input, or of a legacy lamp or rock, nothing happens if it already has
+    /// this state
+    pub fn set(&mut self, node_id: NodeId, is_on: bool) -> Result<(), Error> {
+        let index = *self
+            .indexes
+            .get(&node_id)
+            .ok_or(Error::NodeNotFound(node_id))? as usize;
+        match &mut self.nodes[index] {
+            DenseNode::Input(was_on) => {
+                if *was_on == is_on {
+                    return Ok(());
+                }
+                *was_on = is_on;
+            }
+            DenseNode::LogicBlock(data) if matches!(*data & 0b11111, 0b00000 | 0b11111) => {
+                if (*data & 0b11111 == 0b11111) == is_on {
+                    return Ok(());
+                }
+                *data ^= 0b11111;
+            }
+            _ => return Err(Error::NotAnInput(node_id)),
+        }
+        self.propagate_change(index, is_on);
+        Ok(())
+    }
+
+    /// set the state of a clock, the dense graph doesn't drive them
+    pub fn set_clock(&mut self, node_id: NodeId, is_on: bool) {
+        let index = self.get_index(node_id);
+        let DenseNode::Clock(was_on) = &mut self.nodes[index] else {
+            panic!("the node {} is not a clock", node_id);
+        };
+        if *was_on == is_on {
+            return;
+        }
+        *was_on = is_on;
+        self.propagate_change(index, is_on);
+    }
+
+    /// apply the pending changes, returns the number of actions applied
+    pub fn apply_changes(&mut self) -> usize {
+        let mut nb_actions = 0;
+        while let Some((is_increase, index)) = self.actions_queue.pop_front() {
+            nb_actions += 1;
+            let index = index as usize;
+            match self.nodes[index] {
+                DenseNode::LogicBlock(data) => {
+                    let was_on = self.nodes[index].is_on();
+                    let value = data >> 5;
+                    let value = if is_increase { value + 1 } else { value - 1 };
+                    assert!(value < 5);
+                    self.nodes[index] = DenseNode::LogicBlock(value << 5 | data & 0b11111);
+                    let is_on = self.nodes[index].is_on();
+                    if was_on != is_on {
+                        self.propagate_change(index, is_on);
+                    }
+                }
+                DenseNode::StoringBlock { is_on: was_on, .. } => {
+                    self.sample_source(index);
+                    let DenseNode::StoringBlock {
+                        is_on,
+                        sampled_value,
+                        ..
+                    } = &mut self.nodes[index]
+                    else {
+                        unreachable!();
+                    };
+                    if let Some(sampled_value) = sampled_value.take() {
+                        *is_on = sampled_value;
+                    }
+                    let is_on = *is_on;
+                    if was_on != is_on {
+                        self.propagate_change(index, is_on);
+                    }
+                }
+                DenseNode::Clock(_) => unreachable!("a clock can't have parent nodes"),
+                DenseNode::Input(_) => unreachable!("an input can't have parent nodes"),
+            }
+        }
+        nb_actions
+    }
+
+    /// write the states of the dense graph back in the graph it was made from
+    /// the graph must not have been modified since
+    pub fn write_to(&self, graph: &mut Graph) {
+        for (index, node) in self.nodes.iter().enumerate() {
+            let node_id = self.ids[index];
+            match (*node, graph.get_mut_node(node_id)) {
+                (DenseNode::LogicBlock(data), Some(Node::LogicBlock(old_node))) => {
+                    old_node.set_requirements(data & 0b11111);
+                    old_node.set_value(data >> 5);
+                }
+                (
+                    DenseNode::StoringBlock {
+                        is_on,
+                        was_button_on,
+                        sampled_value,
+                        ..
+                    },
+                    Some(Node::StoringBlock(old_node)),
+                ) => {
+                    old_node.is_on = is_on;
+                    old_node.was_button_on = was_button_on;
+                    old_node.sampled_value = sampled_value;
+                }
+                (DenseNode::Clock(is_on), Some(Node::Clock(old_node))) => {
+                    old_node.is_on = is_on;
+                }
+                (DenseNode::Input(is_on), Some(Node::Input(old_node))) => {
+                    old_node.is_on = is_on;
+                }
+                _ => panic!("the graph has been modified ({})", node_id),
+            }
+        }
+    }
+
+    fn get_index(&self, node_id: NodeId) -> usize {
+        *self.indexes.get(&node_id).expect("node not found") as usize
+    }
+
+    /// the storing blocks whose button node is the node sample their source before the change
+    /// is propagated, like in the `Graph`
+    fn propagate_change(&mut self, index: usize, is_on: bool) {
+        let start = self.children_offsets[index] as usize;
+        let end = self.children_offsets[index + 1] as usize;
+        for i in start..end {
+            let child = self.children[i] as usize;
+            if let DenseNode::StoringBlock { button_node, .. } = self.nodes[child] {
+                if button_node as usize == index {
+                    self.sample_source(child);
+                }
+            }
+        }
+        for child in &self.children[start..end] {
+            self.actions_queue.push_back((is_on, *child));
+        }
+    }
+
+    /// save the state of the source of the storing block if it is sampling
+    fn sample_source(&mut self, index: usize) {
+        let DenseNode::StoringBlock {
+            source,
+            button_node,
+            ..
+        } = self.nodes[index]
+        else {
+            panic!("the node {} is not a storing block", self.ids[index]);
+        };
+        let is_button_on = self.nodes[button_node as usize].is_on();
+        let is_source_on = self.nodes[source as usize].is_on();
+        let DenseNode::StoringBlock {
+            trigger,
+            was_button_on,
+            sampled_value,
+            ..
+        } = &mut self.nodes[index]
+        else {
+            unreachable!();
+        };
+        let is_sampling = match trigger {
+            Trigger::Level => is_button_on,
+            Trigger::RisingEdge => is_button_on && !*was_button_on,
+            Trigger::FallingEdge => !is_button_on && *was_button_on,
+        };
+        if is_sampling {
+            *sampled_value = Some(is_source_on);
+        }
+        *was_button_on = is_button_on;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_adder() {
+        let mut map = init_map("./components/adder.pc");
+        let mut dense = DenseGraph::from(&map);
+        assert!(dense.len() == 32);
+        for node_id in [6, 7, 9, 11, 14] {
+            map.turn_on_lamp(node_id);
+            dense.turn_on_lamp(node_id);
+        }
+        let changes = map.apply_changes();
+        assert!(dense.apply_changes() == changes.nb_events);
+        for node_id in map.get_node_ids() {
+            assert!(map.get_node(node_id).unwrap().is_on() == dense.is_on(node_id));
+        }
+        // 96 + 37 = 133
+        assert!(dense.is_on(17));
+        assert!(dense.is_on(19));
+        assert!(dense.is_on(24));
+        assert!(dense.get_value(24) == Some(1));
+    }
+
+    #[test]
+    fn test_saver() {
+        let mut map = init_map("./components/saver.pc");
+        let mut dense = DenseGraph::from(&map);
+        for node_id in [6, 7, 9] {
+            map.turn_on_lamp(node_id);
+            dense.turn_on_lamp(node_id);
+        }
+        map.apply_changes();
+        dense.apply_changes();
+        for node_id in [6, 7, 9] {
+            map.turn_off_lamp(node_id);
+            dense.turn_off_lamp(node_id);
+        }
+        map.apply_changes();
+        dense.apply_changes();
+        for node_id in map.get_node_ids() {
+            assert!(map.get_node(node_id).unwrap().is_on() == dense.is_on(node_id));
+        }
+        // the storing blocks kept 96
+        assert!(dense.is_on(15));
+        assert!(!dense.is_on(14));
+        assert!(dense.get_value(15) == Some(1));
+        assert!(dense.get_value(23).is_none());
+    }
+
+    #[test]
+    fn test_inputs() {
+        let mut map = init_map("./components/input.pc");
+        let mut dense = DenseGraph::from(&map);
+        dense.set(1, true).unwrap();
+        dense.set(1, true).unwrap();
+        assert!(dense.apply_changes() == 1);
+        assert!(dense.is_on(3));
+        assert!(dense.set(3, false) == Err(Error::NotAnInput(3)));
+        dense.write_to(&mut map);
+        assert!(map.get_node(1).unwrap().is_on());
+        assert!(map.get_node(3).unwrap().is_on());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut map = init_map("./components/toggle.pc");
+        let mut dense = DenseGraph::from(&map);
+        // one rising edge of the clock
+        dense.set_clock(1, false);
+        dense.apply_changes();
+        dense.set_clock(1, true);
+        dense.apply_changes();
+        assert!(dense.is_on(6));
+        dense.write_to(&mut map);
+        assert!(map.get_node(6).unwrap().is_on());
+        assert!(!map.get_node(3).unwrap().is_on());
+        assert!(map.get_logical_block(3).unwrap().get_value() == 1);
+    }
+
+    #[test]
+    fn test_shift_register() {
+        let mut map = init_map("./components/shift.pc");
+        let mut dense = DenseGraph::from(&map);
+        for is_on in [true, true, false, true] {
+            for (node_id, is_on) in [(1, is_on), (2, true), (2, false)] {
+                map.set(node_id, is_on).unwrap();
+                map.apply_changes();
+                dense.set(node_id, is_on).unwrap();
+                dense.apply_changes();
+            }
+        }
+        for node_id in map.get_node_ids() {
+            assert!(map.is_on(node_id) == dense.is_on(node_id));
+        }
+        assert!(dense.is_on(4) && !dense.is_on(5) && dense.is_on(6));
+    }
+
+    #[test]
+    fn test_edge_triggered() {
+        // the node 3 samples its source before the input changes it and the node 6 after
+        let mut map = init_map("./components/edge.pc");
+        let mut dense = DenseGraph::from(&map);
+        for is_on in [true, false, true] {
+            map.set(1, is_on).unwrap();
+            map.apply_changes();
+            dense.set(1, is_on).unwrap();
+            dense.apply_changes();
+            for node_id in map.get_node_ids() {
+                assert!(map.is_on(node_id) == dense.is_on(node_id));
+            }
+        }
+        assert!(dense.is_on(3) && dense.is_on(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "forced nodes")]
+    fn test_forced_nodes() {
+        let mut map = init_map("./components/adder.pc");
+        map.force(25, true);
+        map.apply_changes();
+        let _ = DenseGraph::from(&map);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::Graph;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+type NodeId = u32;
+
+/// how the nodes of a display are shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DisplayKind {
+    /// a digit, the nodes being the segments a to g
+    SevenSegment,
+    /// a row of leds
+    LedBar,
+    /// a framebuffer, the nodes being the pixels row by row
+    Pixels { width: usize, height: usize },
+}
+
+/// a group of nodes shown together in the terminal
+#[derive(Debug)]
+pub struct Display {
+    pub name: String,
+    pub kind: DisplayKind,
+    pub nodes: Vec<NodeId>,
+}
+
+impl Display {
+    pub fn new(name: &str, kind: DisplayKind, nodes: Vec<NodeId>) -> Display {
+        let nb_nodes = match kind {
+            DisplayKind::SevenSegment => 7,
+            DisplayKind::LedBar => nodes.len().max(1),
+            DisplayKind::Pixels { width, height } => width * height,
+        };
+        assert!(
+            nodes.len() == nb_nodes,
+            "the display {} needs {} nodes",
+            name,
+            nb_nodes
+        );
+        Display {
+            name: name.to_string(),
+            kind,
+            nodes,
+        }
+    }
+
+    /* getters */
+    /// the lines showing the display when its nodes have the given states
+    pub fn render(&self, states: &[bool]) -> Vec<String> {
+        let get = |is_on: bool, on: char| if is_on { on } else { ' ' };
+        match self.kind {
+            DisplayKind::SevenSegment => {
+                let [a, b, c, d, e, f, g] = states.try_into().unwrap();
+                vec![
+                    format!(" {} ", get(a, '_')),
+                    format!("{}{}{}", get(f, '|'), get(g, '_'), get(b, '|')),
+                    format!("{}{}{}", get(e, '|'), get(d, '_'), get(c, '|')),
+                ]
+            }
+            DisplayKind::LedBar => vec![states
+                .iter()
+                .map(|is_on| if *is_on { '#' } else { '.' })
+                .collect()],
+            DisplayKind::Pixels { width, .. } => states
+                .chunks(width)
+                .map(|row| row.iter().map(|is_on| get(*is_on, '#')).collect())
+                .collect(),
+        }
+    }
+}
+
+/// run the graph for `ticks` ticks, waiting `tick_duration` after each one, and write its displays
+/// to the terminal every time they change
+/// the keyboards are updated at the start of every tick
+pub fn run_displays(graph: &mut Graph, ticks: u64, tick_duration: Duration, out: &mut impl Write) {
+    let mut screen = None;
+    for tick in 0..=ticks {
+        if tick > 0 {
+            thread::sleep(tick_duration);
+            graph.update_keyboards();
+            graph.run_for(1);
+        }
+        graph.apply_changes();
+        let new_screen = graph.render_displays();
+        if screen.as_ref() == Some(&new_screen) {
+            continue;
+        }
+        // clear the terminal before drawing
+        writeln!(out, "\x1b[2J\x1b[H{}tick {}", new_screen, graph.get_time())
+            .and_then(|_| out.flush())
+            .expect("Failed to write the displays");
+        screen = Some(new_screen);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_render() {
+        let digit = Display::new("digit", DisplayKind::SevenSegment, (1..=7).collect());
+        // 2 is made of the segments a, b, d, e and g
+        let two = [true, true, false, true, true, false, true];
+        assert!(digit.render(&two) == [" _ ", " _|", "|_ "]);
+
+        let leds = Display::new("leds", DisplayKind::LedBar, vec![1, 2, 3]);
+        assert!(leds.render(&[true, false, true]) == ["#.#"]);
+
+        let kind = DisplayKind::Pixels {
+            width: 2,
+            height: 2,
+        };
+        let screen = Display::new("screen", kind, vec![1, 2, 3, 4]);
+        assert!(screen.render(&[true, false, false, true]) == ["# ", " #"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_number_of_nodes() {
+        Display::new("digit", DisplayKind::SevenSegment, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_run_displays() {
+        let mut map = init_map("./components/display.pc");
+        let mut out = Vec::new();
+        run_displays(&mut map, 8, Duration::ZERO, &mut out);
+        let out = String::from_utf8(out).unwrap();
+        // the clock changes every 2 ticks
+        assert!(out.matches("\x1b[2J").count() == 5);
+        assert!(out.starts_with("\x1b[2J\x1b[Hblink\n#.\ntick 0\n"));
+        assert!(out.contains("blink\n.#\ntick 2\n"));
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+type NodeId = u32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    NodeNotFound(NodeId),
+    /// the node is neither an input nor a legacy lamp or rock
+    NotAnInput(NodeId),
+    /// the node is read by another node, by a memory or by a peripheral
+    NodeInUse(NodeId),
+    LinkNotFound(NodeId, NodeId),
+    /// the last link from the button or the source of a storing block toward it
+    NeededLink(NodeId, NodeId),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NodeNotFound(node_id) => write!(f, "node not found ({})", node_id),
+            Error::NotAnInput(node_id) => write!(f, "the node {} is not an input", node_id),
+            Error::NodeInUse(node_id) => write!(f, "the node {} is in use", node_id),
+            Error::LinkNotFound(from, to) => write!(f, "there is no link from {} to {}", from, to),
+            Error::NeededLink(from, to) => {
+                write!(f, "the node {} needs the link from {}", to, from)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
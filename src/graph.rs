@@ -1,10 +1,41 @@
+use crate::Clock;
+use crate::Display;
+use crate::Error;
+use crate::Keyboard;
 use crate::LogicBlock;
+use crate::Memory;
 use crate::Node;
 use crate::StoringBlock;
+use crate::Transmitter;
+use crate::Trigger;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::mem;
+
+mod changes;
+mod coalescing;
+mod debugger;
+mod displays;
+mod editing;
+mod forcing;
+mod keyboards;
+mod memories;
+mod node_map;
+mod observers;
+mod parents;
+mod randoms;
+mod transmitters;
+mod vcd;
+
+pub use changes::ChangeSet;
+pub use debugger::Breakpoint;
 
 type NodeId = u32;
+// (timestamp, insertion order, action, node, depth), the insertion order keeps the actions of a
+// same timestamp in the order they have been queued
+type TimedAction = (u64, u64, NodeAction, NodeId, usize);
 
 #[derive(Debug, Copy, Clone)]
 pub enum ChangeValue {
@@ -12,101 +43,304 @@ pub enum ChangeValue {
     DecreaseValue,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NodeAction {
     InitNode,
     IncreaseValue,
     DecreaseValue,
 }
 
+/// how the changes are propagated through the graph
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SimulationMode {
+    /// every change is propagated instantly, in the order it has been queued
+    ZeroDelay,
+    /// every node takes its delay (in ticks) to react to a change of its inputs
+    Timed,
+    /// the logic blocks are evaluated level by level and the actions queued for a node are merged
+    /// until it is evaluated, so each logic block outside of the loops is evaluated once per
+    /// change with the net change of its value, the other nodes come after them
+    Coalesced,
+}
+
 #[derive(Debug)]
 pub struct Graph {
-    nodes: HashMap<u32, Node>,
-    actions_queue: VecDeque<(NodeAction, NodeId)>,
+    nodes: node_map::NodeMap<Node>,
+    // the nodes having a link toward the node, once per link
+    parents: node_map::NodeMap<Vec<NodeId>>,
+    // (action, node, depth), the depth being the number of actions that led to this one
+    actions_queue: VecDeque<(NodeAction, NodeId, usize)>,
+    mode: SimulationMode,
+    time: u64,
+    timed_queue: BinaryHeap<Reverse<TimedAction>>,
+    nb_timed_actions: u64,
+    delays: node_map::NodeMap<u64>,
+    waves: coalescing::Waves,
+    logic_block_delay: u64,
+    storing_block_delay: u64,
+    clocks: Vec<NodeId>,
+    randoms: Vec<NodeId>,
+    seed: u64,
+    memories: Vec<Memory>,
+    displays: Vec<Display>,
+    keyboards: Vec<Keyboard>,
+    transmitters: Vec<Transmitter>,
+    // transmitters whose strobe turned on in the current wave, they send once it is applied
+    strobed_transmitters: Vec<usize>,
+    names: HashMap<String, NodeId>,
+    // the state the forced nodes show to their children whatever their own state is
+    forced: node_map::NodeMap<bool>,
+    breakpoints: node_map::NodeMap<Breakpoint>,
+    hit_breakpoint: Option<(NodeId, bool)>,
+    // number of times the changes have been applied in zero delay mode
+    nb_waves: u64,
+    recorder: Option<vcd::Recorder>,
+    observers: observers::Observers,
+    is_initialised: bool,
+    // nodes whose read nodes are not all inserted yet, linked once they are
+    unlinked_nodes: Vec<NodeId>,
+    // depth of the action being applied
+    current_depth: usize,
+    changes: changes::ChangesTracker,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Graph {
     pub fn new() -> Self {
         Graph {
-            nodes: HashMap::new(),
+            nodes: node_map::NodeMap::default(),
+            parents: node_map::NodeMap::default(),
             actions_queue: VecDeque::new(),
+            mode: SimulationMode::ZeroDelay,
+            time: 0,
+            timed_queue: BinaryHeap::new(),
+            nb_timed_actions: 0,
+            delays: node_map::NodeMap::default(),
+            waves: coalescing::Waves::default(),
+            logic_block_delay: 1,
+            storing_block_delay: 1,
+            clocks: Vec::new(),
+            randoms: Vec::new(),
+            seed: 0,
+            memories: Vec::new(),
+            displays: Vec::new(),
+            keyboards: Vec::new(),
+            transmitters: Vec::new(),
+            strobed_transmitters: Vec::new(),
+            names: HashMap::new(),
+            forced: node_map::NodeMap::default(),
+            breakpoints: node_map::NodeMap::default(),
+            hit_breakpoint: None,
+            nb_waves: 0,
+            recorder: None,
+            observers: observers::Observers::default(),
+            is_initialised: false,
+            unlinked_nodes: Vec::new(),
+            current_depth: 0,
+            changes: changes::ChangesTracker::default(),
         }
     }
 
     /* getters */
     pub fn get_node(&self, key: u32) -> Option<&Node> {
-        self.nodes.get(&key)
+        self.nodes.get(key)
+    }
+
+    /// the state the node shows to its children, the forced state if it is forced
+    pub fn is_on(&self, node_id: NodeId) -> bool {
+        if let Some(is_on) = self.forced.get(node_id) {
+            return *is_on;
+        }
+        self.get_node(node_id).expect("node not found").is_on()
+    }
+
+    /// the ids of all the nodes, sorted
+    pub fn get_node_ids(&self) -> Vec<NodeId> {
+        self.nodes.keys().collect()
     }
 
-    fn get_mut_node(&mut self, key: u32) -> Option<&mut Node> {
-        self.nodes.get_mut(&key)
+    pub(crate) fn get_mut_node(&mut self, key: u32) -> Option<&mut Node> {
+        self.nodes.get_mut(key)
     }
 
     pub fn get_logical_block(&self, key: u32) -> Option<&LogicBlock> {
-        match self.nodes.get(&key)? {
+        match self.nodes.get(key)? {
             Node::LogicBlock(node) => Some(node),
-            Node::StoringBlock(_) => None,
+            _ => None,
         }
     }
 
     fn get_mut_logical_block(&mut self, key: u32) -> Option<&mut LogicBlock> {
-        match self.nodes.get_mut(&key)? {
+        match self.nodes.get_mut(key)? {
             Node::LogicBlock(node) => Some(node),
-            Node::StoringBlock(_) => None,
+            _ => None,
         }
     }
 
     pub fn get_storing_block(&self, key: u32) -> Option<&StoringBlock> {
-        match self.nodes.get(&key)? {
-            Node::LogicBlock(_) => None,
+        match self.nodes.get(key)? {
             Node::StoringBlock(node) => Some(node),
+            _ => None,
         }
     }
 
     fn get_mut_storing_block(&mut self, key: u32) -> Option<&mut StoringBlock> {
-        match self.nodes.get_mut(&key)? {
-            Node::LogicBlock(_) => None,
+        match self.nodes.get_mut(key)? {
             Node::StoringBlock(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub fn get_clock(&self, key: u32) -> Option<&Clock> {
+        match self.nodes.get(key)? {
+            Node::Clock(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    fn get_mut_clock(&mut self, key: u32) -> Option<&mut Clock> {
+        match self.nodes.get_mut(key)? {
+            Node::Clock(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub fn get_simulation_mode(&self) -> SimulationMode {
+        self.mode
+    }
+
+    /// the current time of the simulation in ticks
+    pub fn get_time(&self) -> u64 {
+        self.time
+    }
+
+    /// the number of ticks the node takes to react to a change of its inputs in timed mode
+    pub fn get_delay(&self, node_id: NodeId) -> u64 {
+        if let Some(delay) = self.delays.get(node_id) {
+            return *delay;
+        }
+        match self.get_node(node_id).expect("node not found") {
+            Node::LogicBlock(_) | Node::Memory(_) => self.logic_block_delay,
+            Node::StoringBlock(_) | Node::Random(_) => self.storing_block_delay,
+            Node::Clock(_) | Node::Input(_) => 0,
+        }
+    }
+
+    pub fn has_pending_actions(&self) -> bool {
+        match self.mode {
+            SimulationMode::ZeroDelay => !self.actions_queue.is_empty(),
+            SimulationMode::Timed => !self.timed_queue.is_empty(),
+            SimulationMode::Coalesced => !self.waves.is_empty(),
         }
     }
 
     /* pub methods */
     pub fn insert_nodes(&mut self, nodes: Vec<(Node, NodeId)>) {
+        let mut batch = mem::take(&mut self.unlinked_nodes);
+        batch.extend(nodes.iter().map(|(_, id)| *id));
         for (node, id) in nodes {
-            assert!(!self.nodes.contains_key(&id));
+            assert!(!self.nodes.contains_key(id));
+            if matches!(node, Node::Clock(_)) {
+                self.clocks.push(id);
+            }
+            let is_random = matches!(node, Node::Random(_));
+            for child in node.get_children() {
+                self.parents.get_or_insert_default(*child).push(id);
+            }
             self.nodes.insert(id, node);
+            if is_random {
+                self.randoms.push(id);
+                self.seed_random_node(id);
+            }
         }
+        // only the new nodes are linked, those using nodes of a later batch wait for them
+        for node_id in batch {
+            let read_nodes = self.get_read_nodes(node_id);
+            if read_nodes
+                .iter()
+                .all(|parent| self.nodes.contains_key(*parent))
+            {
+                self.link_read_nodes(node_id);
+            } else {
+                self.unlinked_nodes.push(node_id);
+            }
+        }
+        self.waves.forget_ranks();
     }
 
     pub fn insert_links(&mut self, links: Vec<(NodeId, NodeId)>) {
+        self.waves.forget_ranks();
         for link in links {
-            assert!(self.nodes.contains_key(&link.0));
-            assert!(self.nodes.contains_key(&link.1));
-
-            let is_first_storing_block =
-                matches!(self.nodes.get(&link.0).unwrap(), Node::StoringBlock(_));
-            let is_second_storing_block =
-                matches!(self.nodes.get(&link.1).unwrap(), Node::StoringBlock(_));
-            assert!(!is_first_storing_block || !is_second_storing_block);
-
-            if is_first_storing_block {
-                let first_node = self.get_mut_storing_block(link.0).unwrap();
-                first_node.children.push(link.1);
-            } else {
-                let first_node = self.get_mut_logical_block(link.0).unwrap();
-                first_node.children.push(link.1);
+            assert!(self.nodes.contains_key(link.0));
+            assert!(self.nodes.contains_key(link.1));
+
+            let is_second_clock = matches!(self.nodes.get(link.1).unwrap(), Node::Clock(_));
+            assert!(!is_second_clock, "a clock can't have parent nodes");
+            let is_second_input = matches!(self.nodes.get(link.1).unwrap(), Node::Input(_));
+            assert!(!is_second_input, "an input can't have parent nodes");
+
+            self.parents.get_or_insert_default(link.1).push(link.0);
+            let first_node = self.get_mut_node(link.0).unwrap();
+            first_node.get_mut_children().push(link.1);
+            // once initialised the children count their parents that are on
+            if self.is_on(link.0) && self.is_initialised {
+                self.push_action(NodeAction::IncreaseValue, link.1);
             }
         }
     }
 
+    /// switch between the zero delay and the timed simulation
+    /// the pending changes must have been applied before
+    pub fn set_simulation_mode(&mut self, mode: SimulationMode) {
+        assert!(
+            !self.has_pending_actions(),
+            "can't change the simulation mode while changes are pending"
+        );
+        self.mode = mode;
+    }
+
+    /// set the delay (in ticks) of a single node, it overrides the delay of its kind
+    pub fn set_delay(&mut self, node_id: NodeId, delay: u64) {
+        assert!(self.nodes.contains_key(node_id), "node not found");
+        self.delays.insert(node_id, delay);
+    }
+
+    /// set the delay (in ticks) of every logic block without a delay of its own
+    pub fn set_logic_block_delay(&mut self, delay: u64) {
+        self.logic_block_delay = delay;
+    }
+
+    /// set the delay (in ticks) of every storing block without a delay of its own
+    pub fn set_storing_block_delay(&mut self, delay: u64) {
+        self.storing_block_delay = delay;
+    }
+
     /// init the value of the nodes in the graph
     /// to do only once and if and only if all the nodes have adden
+    /// the initial state is always computed without delays
     pub fn init_graph_state(&mut self) {
-        for node_id in self.nodes.keys() {
-            self.actions_queue
-                .push_back((NodeAction::InitNode, *node_id));
+        // panics on the nodes still using unknown nodes
+        for node_id in mem::take(&mut self.unlinked_nodes) {
+            self.link_read_nodes(node_id);
+        }
+        let mode = self.mode;
+        self.mode = SimulationMode::ZeroDelay;
+        // sorted so the initial state of the storing blocks doesn't depend on the hashmap order
+        for node_id in self.get_node_ids() {
+            self.push_action(NodeAction::InitNode, node_id);
         }
         self.do_actions();
+        for node_id in self.get_node_ids() {
+            self.update_was_button_on(node_id);
+        }
+        self.mode = mode;
+        self.is_initialised = true;
+        self.take_change_set();
     }
 
     pub fn turn_on_lamp(&mut self, node_id: u32) {
@@ -124,10 +358,7 @@ impl Graph {
             node_id
         );
         node.set_requirements(0b11111);
-        for child in node.children.clone() {
-            self.actions_queue
-                .push_back((NodeAction::IncreaseValue, child));
-        }
+        self.propagate_change(node_id, true);
     }
 
     pub fn turn_off_lamp(&mut self, node_id: u32) {
@@ -145,38 +376,274 @@ impl Graph {
             node_id
         );
         node.set_requirements(0b00000);
-        for child in node.children.clone() {
-            self.actions_queue
-                .push_back((NodeAction::DecreaseValue, child));
+        self.propagate_change(node_id, false);
+    }
+
+    /// set the state of an input, or of a legacy lamp or rock, nothing happens if it already has
+    /// this state
+    pub fn set(&mut self, node_id: NodeId, is_on: bool) -> Result<(), Error> {
+        match self
+            .get_mut_node(node_id)
+            .ok_or(Error::NodeNotFound(node_id))?
+        {
+            Node::Input(node) => {
+                if node.is_on == is_on {
+                    return Ok(());
+                }
+                node.is_on = is_on;
+            }
+            Node::LogicBlock(node) if matches!(node.get_requirements(), 0b00000 | 0b11111) => {
+                if node.is_on() == is_on {
+                    return Ok(());
+                }
+                node.set_requirements(if is_on { 0b11111 } else { 0b00000 });
+            }
+            _ => return Err(Error::NotAnInput(node_id)),
         }
+        self.propagate_change(node_id, is_on);
+        Ok(())
+    }
+
+    /// invert the state of an input, or of a legacy lamp or rock, and return its new state
+    pub fn toggle(&mut self, node_id: NodeId) -> Result<bool, Error> {
+        let node = self.get_node(node_id).ok_or(Error::NodeNotFound(node_id))?;
+        let is_on = !node.is_on();
+        self.set(node_id, is_on)?;
+        Ok(is_on)
     }
 
-    pub fn apply_changes(&mut self) {
+    /// apply the pending changes and report every change since the previous call
+    pub fn apply_changes(&mut self) -> ChangeSet {
         self.do_actions();
+        self.take_change_set()
+    }
+
+    /// apply the changes happening during the next `ticks` ticks and drive the clocks
+    /// in zero delay mode all the changes caused by a clock edge are applied at the tick of the edge
+    /// the random nodes without clock draw a new state at every tick
+    pub fn run_for(&mut self, ticks: u64) {
+        let end = self.time + ticks;
+        loop {
+            let next_tick = self.has_unclocked_random_nodes().then_some(self.time + 1);
+            let edge = self
+                .get_next_clock_edge()
+                .into_iter()
+                .chain(next_tick)
+                .min()
+                .filter(|edge| *edge <= end);
+            self.do_actions_until(edge.unwrap_or(end));
+            let Some(edge) = edge else {
+                break;
+            };
+            self.time = edge;
+            self.update_clocks();
+            self.draw_random_nodes();
+        }
+        self.time = end;
+    }
+
+    /// run the graph for `cycles` full periods of the given clock
+    pub fn run_cycles(&mut self, clock_id: NodeId, cycles: u64) {
+        let period = self.get_clock(clock_id).expect("clock not found").period;
+        self.run_for(period * cycles);
+    }
+
+    /// apply the changes until no more change is pending
+    /// the clocks are not driven, use `run_for` or `run_cycles` for that
+    /// returns the time at which the graph became stable
+    pub fn run_until_stable(&mut self) -> u64 {
+        self.do_actions();
+        self.time
     }
 
     /* privte methods*/
+    fn push_action(&mut self, action: NodeAction, node_id: NodeId) {
+        let depth = self.current_depth + 1;
+        match self.mode {
+            SimulationMode::ZeroDelay => self.actions_queue.push_back((action, node_id, depth)),
+            SimulationMode::Timed => {
+                let time = self.time + self.get_delay(node_id);
+                self.timed_queue.push(Reverse((
+                    time,
+                    self.nb_timed_actions,
+                    action,
+                    node_id,
+                    depth,
+                )));
+                self.nb_timed_actions += 1;
+            }
+            SimulationMode::Coalesced => self.push_coalesced_action(action, node_id, depth),
+        }
+    }
+
+    fn pop_action(&mut self) -> Option<(NodeAction, NodeId, usize)> {
+        match self.mode {
+            SimulationMode::ZeroDelay => self.actions_queue.pop_front(),
+            SimulationMode::Timed => {
+                let Reverse((time, _, action, node_id, depth)) = self.timed_queue.pop()?;
+                self.time = time;
+                Some((action, node_id, depth))
+            }
+            SimulationMode::Coalesced => unreachable!("the coalesced actions are merged per node"),
+        }
+    }
+
+    fn get_next_clock_edge(&self) -> Option<u64> {
+        self.clocks
+            .iter()
+            .map(|clock_id| self.get_clock(*clock_id).unwrap().next_edge(self.time))
+            .min()
+    }
+
+    /// set every clock to the state it must have at the current tick
+    fn update_clocks(&mut self) {
+        let time = self.time;
+        for clock_id in self.clocks.clone() {
+            let clock = self.get_mut_clock(clock_id).unwrap();
+            let is_on = clock.is_on_at(time);
+            if clock.is_on == is_on {
+                continue;
+            }
+            clock.is_on = is_on;
+            self.propagate_change(clock_id, is_on);
+        }
+    }
+
+    /// let the debugger, the recorder and the observers know the node changed
+    fn notify_change(&mut self, node_id: NodeId, is_on: bool) {
+        self.check_breakpoint(node_id, is_on);
+        self.record_change(node_id, is_on);
+        self.notify_observers(node_id, is_on);
+        self.track_change(node_id, is_on);
+    }
+
+    /// queue the new state of the node to its children unless the node is forced
+    fn propagate_change(&mut self, node_id: NodeId, is_on: bool) {
+        if self.forced.contains_key(node_id) {
+            return;
+        }
+        self.propagate_output(node_id, is_on);
+    }
+
+    /// queue the new state shown by the node to its children
+    fn propagate_output(&mut self, node_id: NodeId, is_on: bool) {
+        self.notify_change(node_id, is_on);
+        self.sample_sources(node_id, is_on);
+        self.write_memories(node_id, is_on);
+        self.strobe_transmitters(node_id, is_on);
+        let action = match is_on {
+            true => NodeAction::IncreaseValue,
+            false => NodeAction::DecreaseValue,
+        };
+        self.push_children_actions(action, node_id);
+    }
+
+    /// queue the action for every child of the node, the children are taken out of the node
+    /// meanwhile so nothing is allocated
+    fn push_children_actions(&mut self, action: NodeAction, node_id: NodeId) {
+        let children = mem::take(self.get_mut_node(node_id).unwrap().get_mut_children());
+        for child in &children {
+            self.push_action(action, *child);
+        }
+        *self.get_mut_node(node_id).unwrap().get_mut_children() = children;
+    }
+
     /// must only be used when initialising the graph
     fn init_node(&mut self, node_id: u32) {
-        let node = self.get_mut_node(node_id).expect("node not found");
-        if !node.is_on() {
+        if matches!(self.get_node(node_id), Some(Node::Memory(_))) {
+            self.read_memory_output(node_id);
+        }
+        if self.is_on(node_id) {
+            self.push_children_actions(NodeAction::IncreaseValue, node_id);
+        }
+    }
+
+    /// remember the current state of the button node of the storing block, without sampling
+    fn update_was_button_on(&mut self, node_id: NodeId) {
+        let Some(node) = self.get_storing_block(node_id) else {
             return;
+        };
+        let is_button_node_on = self.is_on(node.button_node);
+        self.get_mut_storing_block(node_id).unwrap().was_button_on = is_button_node_on;
+    }
+
+    /// the nodes the node reads the state of instead of counting them, the button and the source
+    /// of a storing block, the address and write enable nodes of the memory of a memory output and
+    /// the clock of a random node
+    fn get_read_nodes(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut read_nodes = match self.get_node(node_id) {
+            Some(Node::StoringBlock(node)) => vec![node.button_node, node.source],
+            Some(Node::Memory(node)) => {
+                let memory = self
+                    .get_memory(node.memory)
+                    .unwrap_or_else(|| panic!("the memory {} doesn't exist", node.memory));
+                memory
+                    .address
+                    .iter()
+                    .chain(&memory.write_enable)
+                    .copied()
+                    .collect()
+            }
+            Some(Node::Random(node)) => node.clock.into_iter().collect(),
+            _ => vec![],
+        };
+        read_nodes.dedup();
+        read_nodes
+    }
+
+    /// add the links from the nodes the node reads toward it if they are missing, so it is
+    /// updated when one of them changes
+    fn link_read_nodes(&mut self, node_id: NodeId) {
+        for parent in self.get_read_nodes(node_id) {
+            let parent_node = self
+                .get_node(parent)
+                .unwrap_or_else(|| panic!("the node {} uses the unknown node {}", node_id, parent));
+            if !parent_node.get_children().contains(&node_id) {
+                self.insert_links(vec![(parent, node_id)]);
+            }
+        }
+    }
+
+    /// let the storing blocks whose button node is the node know its new state
+    /// the ones that must take the value of their source save it now and take it when they are
+    /// updated, so the storing blocks sharing a button node all get the values their sources had
+    /// when the button changed even if they are the source of each other
+    fn sample_sources(&mut self, button_node: NodeId, is_button_node_on: bool) {
+        let children = mem::take(self.get_mut_node(button_node).unwrap().get_mut_children());
+        for child in children.iter().copied() {
+            let Some(node) = self.get_storing_block(child) else {
+                continue;
+            };
+            if node.button_node != button_node {
+                continue;
+            }
+            let is_source_on = self.is_on(node.source);
+            self.sample_source(child, is_button_node_on, is_source_on);
         }
-        for child in node.get_children().clone() {
-            self.actions_queue
-                .push_back((NodeAction::IncreaseValue, child));
+        *self.get_mut_node(button_node).unwrap().get_mut_children() = children;
+    }
+
+    fn sample_source(&mut self, node_id: NodeId, is_button_node_on: bool, is_source_on: bool) {
+        let is_initialised = self.is_initialised;
+        let node = self.get_mut_storing_block(node_id).unwrap();
+        // the edge triggered blocks only sample on the edges happening after the initialisation
+        if node.is_sampling(is_button_node_on) && (is_initialised || node.trigger == Trigger::Level)
+        {
+            node.sampled_value = Some(is_source_on);
         }
+        node.was_button_on = is_button_node_on;
     }
 
     fn update_storing_node_value(&mut self, node_id: u32) -> bool {
         let node = self.get_storing_block(node_id).unwrap();
-        let is_source_on = self.get_logical_block(node.source).unwrap().is_on();
-        let is_button_node_on = self.get_logical_block(node.button_node).unwrap().is_on();
+        let is_source_on = self.is_on(node.source);
+        let is_button_node_on = self.is_on(node.button_node);
+        self.sample_source(node_id, is_button_node_on, is_source_on);
 
         let node = self.get_mut_storing_block(node_id).unwrap();
         let was_on = node.is_on;
-        if is_button_node_on {
-            node.is_on = is_source_on;
+        if let Some(sampled_value) = node.sampled_value.take() {
+            node.is_on = sampled_value;
         }
         let is_on = node.is_on;
 
@@ -184,13 +651,7 @@ impl Graph {
             return is_on;
         }
 
-        let action = match is_on {
-            true => NodeAction::IncreaseValue,
-            false => NodeAction::DecreaseValue,
-        };
-        for child in node.children.clone() {
-            self.actions_queue.push_back((action, child));
-        }
+        self.propagate_change(node_id, is_on);
         is_on
     }
 
@@ -206,13 +667,7 @@ impl Graph {
         if is_on == was_on {
             return;
         }
-        let action = match is_on {
-            true => NodeAction::IncreaseValue,
-            false => NodeAction::DecreaseValue,
-        };
-        for child in node.children.clone() {
-            self.actions_queue.push_back((action, child));
-        }
+        self.propagate_change(node_id, is_on);
     }
 
     fn update_value(&mut self, node_id: u32, change_value: ChangeValue) {
@@ -224,31 +679,92 @@ impl Graph {
             Node::StoringBlock(_) => {
                 self.update_storing_node_value(node_id);
             }
+            Node::Memory(_) => {
+                self.update_memory_output(node_id);
+            }
+            Node::Random(_) => {
+                self.update_random_node(node_id);
+            }
+            Node::Clock(_) => unreachable!("a clock can't have parent nodes"),
+            Node::Input(_) => unreachable!("an input can't have parent nodes"),
         }
     }
 
-    fn do_action(&mut self) {
-        let action = self.actions_queue.pop_front();
-        if action.is_none() {
-            return;
+    fn do_action(&mut self) -> Option<(NodeAction, NodeId)> {
+        let action = match self.mode {
+            SimulationMode::Coalesced => self.do_coalesced_action(),
+            SimulationMode::ZeroDelay | SimulationMode::Timed => self.do_queued_action(),
+        };
+        if !self.is_wave_pending() {
+            self.send_bytes();
         }
-        let (action, node) = action.unwrap();
+        action
+    }
+
+    fn do_queued_action(&mut self) -> Option<(NodeAction, NodeId)> {
+        let (action, node, depth) = self.pop_action()?;
+        self.current_depth = depth;
+        self.record_action(depth);
         match action {
             NodeAction::InitNode => self.init_node(node),
             NodeAction::IncreaseValue => self.update_value(node, ChangeValue::IncreaseValue),
             NodeAction::DecreaseValue => self.update_value(node, ChangeValue::DecreaseValue),
         }
+        self.current_depth = 0;
+        Some((action, node))
+    }
+
+    /// whether actions of the current wave, or of the current tick in timed mode, are pending
+    fn is_wave_pending(&self) -> bool {
+        match self.mode {
+            SimulationMode::ZeroDelay | SimulationMode::Coalesced => self.has_pending_actions(),
+            SimulationMode::Timed => self
+                .timed_queue
+                .peek()
+                .is_some_and(|Reverse((action_time, ..))| *action_time <= self.time),
+        }
     }
 
     fn do_actions(&mut self) {
-        while !self.actions_queue.is_empty() {
+        if !self.has_pending_actions() {
+            // a strobe turned on by `set` may not have queued any action
+            self.send_bytes();
+            return;
+        }
+        while self.has_pending_actions() {
             self.do_action();
         }
+        if self.mode != SimulationMode::Timed {
+            self.nb_waves += 1;
+        }
+    }
+
+    /// apply the pending actions happening at or before `time`
+    fn do_actions_until(&mut self, time: u64) {
+        match self.mode {
+            SimulationMode::ZeroDelay | SimulationMode::Coalesced => self.do_actions(),
+            SimulationMode::Timed => {
+                while self
+                    .timed_queue
+                    .peek()
+                    .is_some_and(|Reverse((action_time, ..))| *action_time <= time)
+                {
+                    self.do_action();
+                }
+                self.send_bytes();
+            }
+        }
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::bool_comparison,
+    clippy::comparison_to_empty,
+    clippy::vec_init_then_push
+)]
 mod tests {
+    use crate::Input;
     use crate::LogicBlock;
 
     use super::*;
@@ -706,6 +1222,323 @@ mod tests {
         assert!(!graph.get_node(24).unwrap().is_on());
     }
 
+    #[test]
+    fn test_timed_glitch() {
+        /*
+         * A > b
+         * A > c
+         * B > c
+         * A = lamp / stone
+         * B = NOT
+         * C = XOR
+         * */
+        let mut graph = Graph::new();
+        let mut nodes = Vec::new();
+
+        let input_a = Node::LogicBlock(LogicBlock::new(0b00000, vec![2, 3]));
+        nodes.push((input_a, 1));
+        let input_b = Node::LogicBlock(LogicBlock::new(0b00001, vec![3]));
+        nodes.push((input_b, 2));
+        let input_c = Node::LogicBlock(LogicBlock::new(0b00010, vec![]));
+        nodes.push((input_c, 3));
+
+        graph.insert_nodes(nodes);
+        graph.set_simulation_mode(SimulationMode::Timed);
+        graph.init_graph_state();
+        assert!(graph.get_time() == 0);
+        assert!(graph.get_node(2).unwrap().is_on());
+        assert!(graph.get_node(3).unwrap().is_on());
+
+        graph.turn_on_lamp(1);
+        graph.run_for(1);
+        // c sees a on before b turned off
+        assert!(!graph.get_node(2).unwrap().is_on());
+        assert!(!graph.get_node(3).unwrap().is_on());
+        graph.run_for(1);
+        assert!(graph.get_node(3).unwrap().is_on());
+        assert!(graph.run_until_stable() == 2);
+
+        // in zero delay mode the glitch still happens but is never observable
+        graph.set_simulation_mode(SimulationMode::ZeroDelay);
+        graph.turn_off_lamp(1);
+        graph.run_for(1);
+        assert!(graph.get_node(2).unwrap().is_on());
+        assert!(graph.get_node(3).unwrap().is_on());
+        assert!(graph.get_time() == 3);
+    }
+
+    #[test]
+    fn test_timed_delays() {
+        /*
+         * A > b
+         * B > c
+         * A = lamp / stone
+         * B, C = buffer
+         * */
+        let mut graph = Graph::new();
+        let mut nodes = Vec::new();
+
+        let input_a = Node::LogicBlock(LogicBlock::new(0b00000, vec![2]));
+        nodes.push((input_a, 1));
+        let input_b = Node::LogicBlock(LogicBlock::new(0b00010, vec![3]));
+        nodes.push((input_b, 2));
+        let input_c = Node::LogicBlock(LogicBlock::new(0b00010, vec![]));
+        nodes.push((input_c, 3));
+
+        graph.insert_nodes(nodes);
+        graph.init_graph_state();
+        graph.set_simulation_mode(SimulationMode::Timed);
+        graph.set_logic_block_delay(3);
+        graph.set_delay(3, 5);
+        assert!(graph.get_delay(2) == 3);
+        assert!(graph.get_delay(3) == 5);
+
+        graph.turn_on_lamp(1);
+        graph.run_for(2);
+        assert!(!graph.get_node(2).unwrap().is_on());
+        graph.run_for(1);
+        assert!(graph.get_node(2).unwrap().is_on());
+        graph.run_for(4);
+        assert!(!graph.get_node(3).unwrap().is_on());
+        graph.run_for(1);
+        assert!(graph.get_node(3).unwrap().is_on());
+        assert!(graph.get_time() == 8);
+
+        graph.turn_off_lamp(1);
+        assert!(graph.run_until_stable() == 16);
+        assert!(!graph.get_node(3).unwrap().is_on());
+    }
+
+    #[test]
+    fn test_timed_clock() {
+        /*
+         * A > b
+         * A = clock
+         * B = buffer
+         * */
+        let mut graph = Graph::new();
+        let mut nodes = Vec::new();
+
+        let input_a = Node::Clock(Clock::new(6, 1, vec![2]));
+        nodes.push((input_a, 1));
+        let input_b = Node::LogicBlock(LogicBlock::new(0b00010, vec![]));
+        nodes.push((input_b, 2));
+
+        graph.insert_nodes(nodes);
+        graph.set_simulation_mode(SimulationMode::Timed);
+        graph.init_graph_state();
+        assert!(!graph.get_node(1).unwrap().is_on());
+
+        graph.run_for(1);
+        assert!(graph.get_node(1).unwrap().is_on());
+        assert!(!graph.get_node(2).unwrap().is_on());
+        graph.run_for(1);
+        assert!(graph.get_node(2).unwrap().is_on());
+        graph.run_for(2);
+        assert!(!graph.get_node(1).unwrap().is_on());
+        assert!(graph.get_node(2).unwrap().is_on());
+        graph.run_for(1);
+        assert!(!graph.get_node(2).unwrap().is_on());
+
+        // the clocks are not driven while waiting for the graph to be stable
+        assert!(graph.run_until_stable() == 5);
+        graph.run_cycles(1, 2);
+        assert!(graph.get_time() == 17);
+        assert!(!graph.get_node(1).unwrap().is_on());
+        assert!(!graph.get_node(2).unwrap().is_on());
+    }
+
+    #[test]
+    fn test_set_legacy_lamp() {
+        /*
+         * A > c
+         * B > c
+         * A = lamp
+         * B = rock
+         * C = AND
+         * */
+        let mut graph = Graph::new();
+        let mut nodes = Vec::new();
+
+        let input_a = Node::LogicBlock(LogicBlock::new(0b11111, vec![3]));
+        nodes.push((input_a, 1));
+        let input_b = Node::LogicBlock(LogicBlock::new(0b00000, vec![3]));
+        nodes.push((input_b, 2));
+        let input_c = Node::LogicBlock(LogicBlock::new(0b00100, vec![]));
+        nodes.push((input_c, 3));
+
+        graph.insert_nodes(nodes);
+        graph.init_graph_state();
+
+        graph.set(1, true).unwrap();
+        graph.set(2, true).unwrap();
+        graph.apply_changes();
+        assert!(graph.get_node(3).unwrap().is_on());
+        assert!(graph.toggle(1) == Ok(false));
+        assert!(graph.toggle(1) == Ok(true));
+        graph.set(2, false).unwrap();
+        graph.apply_changes();
+        assert!(!graph.get_node(3).unwrap().is_on());
+        assert!(graph.get_logical_block(3).unwrap().get_value() == 1);
+        assert!(graph.set(3, true) == Err(Error::NotAnInput(3)));
+    }
+
+    /// the input 1 is the button and the input 2 the source of the storing block 3, without links
+    fn get_storing_map(trigger: Trigger) -> Graph {
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (Node::Input(Input::new(false, vec![])), 1),
+            (Node::Input(Input::new(false, vec![])), 2),
+            (
+                Node::StoringBlock(StoringBlock::new_with_trigger(false, 2, 1, vec![], trigger)),
+                3,
+            ),
+        ]);
+        map.init_graph_state();
+        map
+    }
+
+    #[test]
+    fn test_storing_block_links() {
+        let map = get_storing_map(Trigger::Level);
+        assert!(map.get_parents(3) == [1, 2]);
+        // the links already there are not added twice
+        let map = crate::init_map("./components/saver.pc");
+        assert!(map.get_parents(18) == [1, 9]);
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (Node::Input(Input::new(true, vec![])), 1),
+            (
+                Node::StoringBlock(StoringBlock::new(false, 1, 1, vec![])),
+                2,
+            ),
+        ]);
+        map.init_graph_state();
+        assert!(map.get_parents(2) == [1]);
+        assert!(map.is_on(2));
+    }
+
+    #[test]
+    fn test_storing_block_later_links() {
+        let mut map = Graph::new();
+        map.insert_nodes(vec![(
+            Node::StoringBlock(StoringBlock::new(false, 3, 2, vec![])),
+            1,
+        )]);
+        map.insert_nodes(vec![(Node::Input(Input::new(true, vec![])), 2)]);
+        map.insert_nodes(vec![(Node::Input(Input::new(true, vec![])), 3)]);
+        map.init_graph_state();
+        assert!(map.get_parents(1) == [2, 3]);
+        assert!(map.is_on(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "uses the unknown node 3")]
+    fn test_storing_block_unknown_link() {
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (
+                Node::StoringBlock(StoringBlock::new(false, 3, 2, vec![])),
+                1,
+            ),
+            (Node::Input(Input::new(true, vec![])), 2),
+        ]);
+        map.init_graph_state();
+    }
+
+    #[test]
+    fn test_remove_storing_block_link() {
+        let mut map = get_storing_map(Trigger::Level);
+        assert!(map.remove_link(2, 3) == Err(Error::NeededLink(2, 3)));
+    }
+
+    #[test]
+    fn test_storing_block_orderings() {
+        // every sequence of 4 changes of the button or the source, applied one by one
+        for trigger in [Trigger::Level, Trigger::RisingEdge, Trigger::FallingEdge] {
+            for sequence in 0..16 {
+                let mut map = get_storing_map(trigger);
+                let (mut is_button_on, mut is_source_on, mut is_on) = (false, false, false);
+                for i in 0..4 {
+                    let was_button_on = is_button_on;
+                    match sequence >> i & 1 {
+                        0 => is_button_on = map.toggle(1).unwrap(),
+                        _ => is_source_on = map.toggle(2).unwrap(),
+                    }
+                    map.apply_changes();
+                    let is_sampling = match trigger {
+                        Trigger::Level => is_button_on,
+                        Trigger::RisingEdge => is_button_on && !was_button_on,
+                        Trigger::FallingEdge => !is_button_on && was_button_on,
+                    };
+                    if is_sampling {
+                        is_on = is_source_on;
+                    }
+                    assert!(
+                        map.is_on(3) == is_on,
+                        "{:?} {:04b} {}",
+                        trigger,
+                        sequence,
+                        i
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_storing_block_simultaneous_changes() {
+        // the source 4 is the inverted button, the block reads it before the button changes it
+        for (trigger, is_on) in [(Trigger::RisingEdge, true), (Trigger::FallingEdge, false)] {
+            let mut map = Graph::new();
+            map.insert_nodes(vec![
+                (Node::Input(Input::new(false, vec![4])), 1),
+                (Node::LogicBlock(LogicBlock::new(0b00001, vec![])), 4),
+                (
+                    Node::StoringBlock(StoringBlock::new_with_trigger(
+                        false,
+                        4,
+                        1,
+                        vec![],
+                        trigger,
+                    )),
+                    3,
+                ),
+            ]);
+            map.init_graph_state();
+            map.set(1, true).unwrap();
+            map.apply_changes();
+            map.set(1, false).unwrap();
+            map.apply_changes();
+            assert!(map.is_on(3) == is_on);
+        }
+    }
+
+    #[test]
+    fn test_shift_register() {
+        // 3 rising edge storing blocks sharing the clock 2, each one being the source of the next
+        for mode in [
+            SimulationMode::ZeroDelay,
+            SimulationMode::Timed,
+            SimulationMode::Coalesced,
+        ] {
+            let mut map = crate::init_map("./components/shift.pc");
+            map.set_simulation_mode(mode);
+            for is_on in [true, false, true] {
+                map.set(1, is_on).unwrap();
+                map.apply_changes();
+                map.set(2, true).unwrap();
+                map.apply_changes();
+                map.set(2, false).unwrap();
+                map.apply_changes();
+            }
+            // every block took the previous state of its source on each edge
+            assert!(map.is_on(4));
+            assert!(!map.is_on(5));
+            assert!(map.is_on(6));
+        }
+    }
+
     //#[test]
     //fn boucle() {
     //    /*
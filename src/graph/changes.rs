@@ -0,0 +1,128 @@
+use super::node_map::NodeMap;
+use super::Graph;
+use super::NodeId;
+
+/// what happened while the changes were applied
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangeSet {
+    /// the nodes whose state is not the same as before with their new state, sorted by node
+    pub changed: Vec<(NodeId, bool)>,
+    /// the number of actions applied
+    pub nb_events: usize,
+    /// the length of the longest chain of actions, each one caused by the previous one
+    pub depth: usize,
+}
+
+impl ChangeSet {
+    /// add the changes applied after these ones, a node changed by both is back to its state
+    /// before the first ones
+    pub fn merge(&mut self, next: ChangeSet) {
+        for (node_id, is_on) in next.changed {
+            match self.changed.binary_search_by_key(&node_id, |(id, _)| *id) {
+                Ok(index) => {
+                    self.changed.remove(index);
+                }
+                Err(index) => self.changed.insert(index, (node_id, is_on)),
+            }
+        }
+        self.nb_events += next.nb_events;
+        self.depth = self.depth.max(next.depth);
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ChangesTracker {
+    // state of the nodes before their first change
+    initial_states: NodeMap<bool>,
+    nb_events: usize,
+    depth: usize,
+}
+
+impl Graph {
+    pub(super) fn record_action(&mut self, depth: usize) {
+        self.changes.nb_events += 1;
+        self.changes.depth = self.changes.depth.max(depth);
+    }
+
+    pub(super) fn track_change(&mut self, node_id: NodeId, is_on: bool) {
+        if !self.changes.initial_states.contains_key(node_id) {
+            self.changes.initial_states.insert(node_id, !is_on);
+        }
+    }
+
+    /// forget the changes of a removed node
+    pub(super) fn untrack_node(&mut self, node_id: NodeId) {
+        self.changes.initial_states.remove(node_id);
+    }
+
+    /// the changes since the last time they have been taken
+    pub(super) fn take_change_set(&mut self) -> ChangeSet {
+        let tracker = std::mem::take(&mut self.changes);
+        let changed = tracker
+            .initial_states
+            .iter()
+            .filter_map(|(node_id, was_on)| {
+                let is_on = self.is_on(node_id);
+                (is_on != *was_on).then_some((node_id, is_on))
+            })
+            .collect::<Vec<(NodeId, bool)>>();
+        ChangeSet {
+            changed,
+            nb_events: tracker.nb_events,
+            depth: tracker.depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_change_set() {
+        let mut map = init_map("./components/adder.pc");
+        // 1 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        let changes = map.apply_changes();
+        // the first sum bit turned on and off so it is not reported
+        assert!(changes.changed == [(1, true), (9, true), (18, true), (25, true)]);
+        assert!(changes.nb_events == 6);
+        assert!(changes.depth == 2);
+
+        assert!(map.apply_changes() == ChangeSet::default());
+
+        // 3 + 1, the carry ripples
+        map.turn_on_lamp(2);
+        let changes = map.apply_changes();
+        assert!(changes.changed == [(2, true), (18, false), (19, true), (26, true)]);
+        assert!(changes.nb_events == 4);
+        assert!(changes.depth == 2);
+
+        // 7 + 1, the carry ripples one bit further
+        map.turn_on_lamp(3);
+        let changes = map.apply_changes();
+        assert!(changes.changed == [(3, true), (19, false), (20, true), (27, true)]);
+        assert!(changes.depth == 2);
+
+        // 7 + 9
+        map.turn_on_lamp(12);
+        let changes = map.apply_changes();
+        assert!(changes.changed == [(12, true), (20, false), (21, true), (28, true)]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut map = init_map("./components/adder.pc");
+        // 1 + 0 then 1 + 1
+        map.turn_on_lamp(1);
+        let mut changes = map.apply_changes();
+        map.turn_on_lamp(9);
+        changes.merge(map.apply_changes());
+        // the first sum bit turned on and off so it is not reported
+        assert!(changes.changed == [(1, true), (9, true), (18, true), (25, true)]);
+        assert!(changes.nb_events == 6);
+        assert!(changes.depth == 2);
+    }
+}
@@ -0,0 +1,246 @@
+use super::Graph;
+use super::NodeAction;
+use super::NodeId;
+use crate::Node;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+
+/// the nodes to evaluate in the coalesced mode, level by level
+#[derive(Debug, Default)]
+pub(super) struct Waves {
+    // (rank, order queued, node) of the nodes to evaluate, the lowest rank first
+    queue: BinaryHeap<Reverse<(usize, u64, NodeId)>>,
+    nb_queued: u64,
+    // the net change of the value, the depth and the last action queued of the nodes in the queue
+    actions: HashMap<NodeId, (i32, usize, NodeAction)>,
+    // the level of the logic blocks outside of the loops, computed when needed
+    ranks: Option<HashMap<NodeId, usize>>,
+}
+
+impl Waves {
+    pub(super) fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub(super) fn remove_node(&mut self, node_id: NodeId) {
+        self.queue.retain(|Reverse((_, _, id))| *id != node_id);
+        self.actions.remove(&node_id);
+    }
+
+    /// the levels must be computed again after the links or the nodes changed
+    pub(super) fn forget_ranks(&mut self) {
+        self.ranks = None;
+    }
+
+    /// the pending actions, one per node, in the order they will be applied
+    pub(super) fn get_actions(&self) -> Vec<(NodeAction, NodeId)> {
+        let mut queue = self
+            .queue
+            .iter()
+            .map(|Reverse(queued)| *queued)
+            .collect::<Vec<(usize, u64, NodeId)>>();
+        queue.sort();
+        queue
+            .into_iter()
+            .map(|(_, _, node_id)| (self.actions[&node_id].2, node_id))
+            .collect()
+    }
+}
+
+fn get_action(delta: i32, last_action: NodeAction) -> NodeAction {
+    match delta {
+        0 => last_action,
+        1.. => NodeAction::IncreaseValue,
+        _ => NodeAction::DecreaseValue,
+    }
+}
+
+impl Graph {
+    /// the logic blocks outside of the loops are evaluated level by level, so all the changes of
+    /// their parents are merged before they are evaluated, the other nodes come after them in the
+    /// order they have been queued
+    fn get_rank(&mut self, node_id: NodeId) -> usize {
+        if self.waves.ranks.is_none() {
+            let (levels, _) = self.get_acyclic_levels();
+            let ranks = levels
+                .into_iter()
+                .enumerate()
+                .flat_map(|(rank, level)| level.into_iter().map(move |node_id| (node_id, rank)))
+                .collect();
+            self.waves.ranks = Some(ranks);
+        }
+        let ranks = self.waves.ranks.as_ref().unwrap();
+        ranks.get(&node_id).copied().unwrap_or(usize::MAX)
+    }
+
+    pub(super) fn push_coalesced_action(
+        &mut self,
+        action: NodeAction,
+        node_id: NodeId,
+        depth: usize,
+    ) {
+        let change = match action {
+            NodeAction::IncreaseValue => 1,
+            NodeAction::DecreaseValue => -1,
+            NodeAction::InitNode => unreachable!("the graph is always initialised without delays"),
+        };
+        if !self.waves.actions.contains_key(&node_id) {
+            let rank = self.get_rank(node_id);
+            let waves = &mut self.waves;
+            waves.queue.push(Reverse((rank, waves.nb_queued, node_id)));
+            waves.nb_queued += 1;
+        }
+        let (delta, max_depth, last_action) = self
+            .waves
+            .actions
+            .entry(node_id)
+            .or_insert((0, depth, action));
+        *delta += change;
+        *max_depth = (*max_depth).max(depth);
+        *last_action = action;
+    }
+
+    /// evaluate the next node with the net change of its value
+    /// the logic blocks whose parents changed but cancel each other are not evaluated
+    pub(super) fn do_coalesced_action(&mut self) -> Option<(NodeAction, NodeId)> {
+        let (node_id, delta, depth, action) = loop {
+            let Reverse((_, _, node_id)) = self.waves.queue.pop()?;
+            let (delta, depth, last_action) = self.waves.actions.remove(&node_id).unwrap();
+            let is_logic_block = matches!(self.get_node(node_id), Some(Node::LogicBlock(_)));
+            if delta != 0 || !is_logic_block {
+                break (node_id, delta, depth, get_action(delta, last_action));
+            }
+        };
+        self.current_depth = depth;
+        self.record_action(depth);
+        match self.get_mut_node(node_id).expect("node not found") {
+            Node::LogicBlock(node) => {
+                let was_on = node.is_on();
+                node.set_value((node.get_value() as i32 + delta) as u8);
+                let is_on = node.is_on();
+                if is_on != was_on {
+                    self.propagate_change(node_id, is_on);
+                }
+            }
+            Node::StoringBlock(_) => {
+                self.update_storing_node_value(node_id);
+            }
+            Node::Memory(_) => {
+                self.update_memory_output(node_id);
+            }
+            Node::Random(_) => {
+                self.update_random_node(node_id);
+            }
+            Node::Clock(_) => unreachable!("a clock can't have parent nodes"),
+            Node::Input(_) => unreachable!("an input can't have parent nodes"),
+        }
+        self.current_depth = 0;
+        Some((action, node_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+    use crate::LogicBlock;
+    use crate::SimulationMode;
+
+    fn add(map: &mut Graph, a: u8, b: u8) -> usize {
+        // the bits of the inputs are on the nodes 1 to 8 and 9 to 16, the least significant first
+        for i in 0..8 {
+            if a >> i & 1 == 1 {
+                map.turn_on_lamp(i + 1);
+            }
+            if b >> i & 1 == 1 {
+                map.turn_on_lamp(i + 9);
+            }
+        }
+        map.apply_changes().nb_events
+    }
+
+    fn get_sum(map: &Graph) -> u8 {
+        (17..=24).rev().fold(0, |sum, i| {
+            sum << 1 | map.get_node(i).unwrap().is_on() as u8
+        })
+    }
+
+    #[test]
+    fn test_coalesced_adder() {
+        let mut map = init_map("./components/adder.pc");
+        let nb_events = add(&mut map, 127, 1);
+        assert!(get_sum(&map) == 128);
+
+        let mut coalesced_map = init_map("./components/adder.pc");
+        coalesced_map.set_simulation_mode(SimulationMode::Coalesced);
+        let nb_coalesced_events = add(&mut coalesced_map, 127, 1);
+        assert!(get_sum(&coalesced_map) == 128);
+        assert!(nb_coalesced_events < nb_events);
+        for node_id in map.get_node_ids() {
+            assert!(
+                map.get_node(node_id).unwrap().is_on()
+                    == coalesced_map.get_node(node_id).unwrap().is_on()
+            );
+        }
+    }
+
+    #[test]
+    fn test_coalesced_levels() {
+        // the xor 4 is reached from the lamp 1 directly and through the buffers 2 and 3
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (Node::LogicBlock(LogicBlock::new(0b00000, vec![2, 4])), 1),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![3])), 2),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![4])), 3),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![])), 4),
+        ]);
+        map.set_simulation_mode(SimulationMode::Coalesced);
+        map.init_graph_state();
+        map.turn_on_lamp(1);
+        // the xor waits for the buffers and sees both of its parents turn on at once
+        let mut evaluated = Vec::new();
+        while let Some((_, node_id)) = map.step() {
+            evaluated.push(node_id);
+        }
+        assert!(evaluated == [2, 3, 4]);
+        assert!(!map.is_on(4));
+        assert!(map.take_change_set().changed == [(1, true), (2, true), (3, true)]);
+
+        // each node of the adder is evaluated once
+        let mut map = init_map("./components/adder.pc");
+        map.set_simulation_mode(SimulationMode::Coalesced);
+        for node_id in 1..=8 {
+            map.turn_on_lamp(node_id);
+        }
+        map.turn_on_lamp(9);
+        let mut evaluated = Vec::new();
+        while let Some((_, node_id)) = map.step() {
+            assert!(!evaluated.contains(&node_id));
+            evaluated.push(node_id);
+        }
+        assert!(get_sum(&map) == 0);
+    }
+
+    #[test]
+    fn test_coalesced_pending_actions() {
+        let mut map = init_map("./components/adder.pc");
+        map.set_simulation_mode(SimulationMode::Coalesced);
+        // 1 + 1, both inputs of the lowest bit of the sum change in the same wave
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        assert!(
+            map.get_pending_actions()
+                == vec![
+                    (0, NodeAction::IncreaseValue, 17),
+                    (0, NodeAction::IncreaseValue, 25)
+                ]
+        );
+        assert!(map.step() == Some((NodeAction::IncreaseValue, 17)));
+        // evaluated once with both inputs on, it never turned on
+        assert!(!map.get_node(17).unwrap().is_on());
+        assert!(map.get_logical_block(17).unwrap().get_value() == 2);
+        map.apply_changes();
+        assert!(get_sum(&map) == 2);
+    }
+}
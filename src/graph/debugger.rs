@@ -0,0 +1,181 @@
+use super::Graph;
+use super::NodeAction;
+use super::NodeId;
+use super::SimulationMode;
+use super::TimedAction;
+use std::cmp::Reverse;
+
+/// when the propagation must stop on a node
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    TurnOn,
+    TurnOff,
+    /// watchpoint, stop on any change of the node
+    Change,
+}
+
+impl Breakpoint {
+    fn is_hit(&self, is_on: bool) -> bool {
+        match self {
+            Breakpoint::TurnOn => is_on,
+            Breakpoint::TurnOff => !is_on,
+            Breakpoint::Change => true,
+        }
+    }
+}
+
+impl Graph {
+    /// give a name to a node so it can be found with `get_node_id`
+    pub fn set_node_name(&mut self, node_id: NodeId, name: &str) {
+        assert!(self.nodes.contains_key(node_id), "node not found");
+        self.names.insert(name.to_string(), node_id);
+    }
+
+    pub fn get_node_id(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).copied()
+    }
+
+    /// stop `run_until_break` when the node changes the way described by the breakpoint
+    /// a node has at most one breakpoint, the new one replaces the old one
+    pub fn add_breakpoint(&mut self, node_id: NodeId, breakpoint: Breakpoint) {
+        assert!(self.nodes.contains_key(node_id), "node not found");
+        self.breakpoints.insert(node_id, breakpoint);
+    }
+
+    /// stop `run_until_break` whenever the node changes
+    pub fn add_watchpoint(&mut self, node_id: NodeId) {
+        self.add_breakpoint(node_id, Breakpoint::Change);
+    }
+
+    pub fn remove_breakpoint(&mut self, node_id: NodeId) {
+        self.breakpoints.remove(node_id);
+    }
+
+    /// the actions waiting to be applied in the order they will be, with the tick they will be
+    /// applied at
+    pub fn get_pending_actions(&self) -> Vec<(u64, NodeAction, NodeId)> {
+        match self.mode {
+            SimulationMode::ZeroDelay => self
+                .actions_queue
+                .iter()
+                .map(|(action, node_id, _)| (self.time, *action, *node_id))
+                .collect(),
+            SimulationMode::Timed => {
+                let mut actions = self
+                    .timed_queue
+                    .iter()
+                    .map(|Reverse(action)| *action)
+                    .collect::<Vec<TimedAction>>();
+                actions.sort();
+                actions
+                    .into_iter()
+                    .map(|(time, _, action, node_id, _)| (time, action, node_id))
+                    .collect()
+            }
+            SimulationMode::Coalesced => self
+                .waves
+                .get_actions()
+                .into_iter()
+                .map(|(action, node_id)| (self.time, action, node_id))
+                .collect(),
+        }
+    }
+
+    /// apply only the next pending action and return it
+    pub fn step(&mut self) -> Option<(NodeAction, NodeId)> {
+        self.do_action()
+    }
+
+    /// apply the pending actions until a breakpoint is hit or the graph is stable
+    /// returns the node that hit the breakpoint with its new state, the actions it queued are
+    /// still pending
+    pub fn run_until_break(&mut self) -> Option<(NodeId, bool)> {
+        self.hit_breakpoint = None;
+        while self.hit_breakpoint.is_none() && self.do_action().is_some() {}
+        self.hit_breakpoint.take()
+    }
+
+    pub(super) fn check_breakpoint(&mut self, node_id: NodeId, is_on: bool) {
+        let Some(breakpoint) = self.breakpoints.get(node_id) else {
+            return;
+        };
+        if breakpoint.is_hit(is_on) {
+            self.hit_breakpoint = Some((node_id, is_on));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_step() {
+        let mut map = init_map("./components/adder.pc");
+        assert!(map.step().is_none());
+        assert!(map.get_pending_actions().is_empty());
+
+        map.turn_on_lamp(1);
+        assert!(
+            map.get_pending_actions()
+                == vec![
+                    (0, NodeAction::IncreaseValue, 17),
+                    (0, NodeAction::IncreaseValue, 25)
+                ]
+        );
+        assert!(map.step() == Some((NodeAction::IncreaseValue, 17)));
+        assert!(map.get_node(17).unwrap().is_on());
+        assert!(map.get_pending_actions() == vec![(0, NodeAction::IncreaseValue, 25)]);
+        assert!(map.step() == Some((NodeAction::IncreaseValue, 25)));
+        // a single input on doesn't make a carry
+        assert!(!map.get_node(25).unwrap().is_on());
+        assert!(map.step().is_none());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut map = init_map("./components/adder.pc");
+        map.set_node_name(25, "carry");
+        map.set_node_name(18, "sum");
+        map.add_breakpoint(map.get_node_id("carry").unwrap(), Breakpoint::TurnOn);
+        map.add_watchpoint(map.get_node_id("sum").unwrap());
+
+        // 1 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        assert!(map.run_until_break() == Some((25, true)));
+        // the carry didn't ripple yet
+        assert!(!map.get_node(18).unwrap().is_on());
+        assert!(map.get_pending_actions().len() == 2);
+        assert!(map.run_until_break() == Some((18, true)));
+        assert!(map.run_until_break().is_none());
+        assert!(map.get_node(18).unwrap().is_on());
+
+        // the carry turning off doesn't break
+        map.remove_breakpoint(18);
+        map.turn_off_lamp(9);
+        assert!(map.run_until_break().is_none());
+        assert!(!map.get_node(25).unwrap().is_on());
+        assert!(!map.get_node(18).unwrap().is_on());
+    }
+
+    #[test]
+    fn test_timed_pending_actions() {
+        let mut map = init_map("./components/adder.pc");
+        map.set_simulation_mode(SimulationMode::Timed);
+        map.set_delay(25, 3);
+        map.turn_on_lamp(1);
+        assert!(
+            map.get_pending_actions()
+                == vec![
+                    (1, NodeAction::IncreaseValue, 17),
+                    (3, NodeAction::IncreaseValue, 25)
+                ]
+        );
+        map.step();
+        assert!(map.get_time() == 1);
+        map.step();
+        assert!(map.get_time() == 3);
+    }
+}
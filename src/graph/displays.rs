@@ -0,0 +1,34 @@
+use super::Graph;
+use crate::Display;
+
+impl Graph {
+    pub fn add_display(&mut self, display: Display) {
+        for node_id in &display.nodes {
+            assert!(self.nodes.contains_key(*node_id), "node not found");
+        }
+        self.displays.push(display);
+    }
+
+    pub fn get_displays(&self) -> &[Display] {
+        &self.displays
+    }
+
+    /// every display with its name above it, as the states of the nodes show it
+    pub fn render_displays(&self) -> String {
+        let mut screen = String::new();
+        for display in &self.displays {
+            let states = display
+                .nodes
+                .iter()
+                .map(|node_id| self.is_on(*node_id))
+                .collect::<Vec<bool>>();
+            screen.push_str(&display.name);
+            screen.push('\n');
+            for line in display.render(&states) {
+                screen.push_str(&line);
+                screen.push('\n');
+            }
+        }
+        screen
+    }
+}
@@ -0,0 +1,359 @@
+use super::Graph;
+use super::NodeAction;
+use super::NodeId;
+use crate::Error;
+use crate::Node;
+
+impl Graph {
+    /// remove one of the links going from `from` to `to`
+    /// the last link from the button or the source of a storing block toward it can't be removed
+    /// the changes it causes are applied with the other pending changes
+    pub fn remove_link(&mut self, from: NodeId, to: NodeId) -> Result<(), Error> {
+        let is_read_node = self.get_read_nodes(to).contains(&from);
+        let node = self.get_mut_node(from).ok_or(Error::NodeNotFound(from))?;
+        let children = node.get_mut_children();
+        let position = children
+            .iter()
+            .position(|child| *child == to)
+            .ok_or(Error::LinkNotFound(from, to))?;
+        if is_read_node && children.iter().filter(|child| **child == to).count() == 1 {
+            return Err(Error::NeededLink(from, to));
+        }
+        children.remove(position);
+        self.waves.forget_ranks();
+        let parents = self.parents.get_mut(to).unwrap();
+        let position = parents.iter().position(|parent| *parent == from).unwrap();
+        parents.remove(position);
+        if self.is_on(from) && self.is_initialised {
+            self.push_action(NodeAction::DecreaseValue, to);
+        }
+        Ok(())
+    }
+
+    /// make one of the links going from `from` to `to` go to `new_to` instead
+    pub fn redirect_link(&mut self, from: NodeId, to: NodeId, new_to: NodeId) -> Result<(), Error> {
+        if !self.nodes.contains_key(new_to) {
+            return Err(Error::NodeNotFound(new_to));
+        }
+        self.remove_link(from, to)?;
+        self.insert_links(vec![(from, new_to)]);
+        Ok(())
+    }
+
+    pub fn set_requirements(&mut self, node_id: NodeId, requirements: u8) {
+        let node = self
+            .get_mut_logical_block(node_id)
+            .expect("only logic blocks have requirements");
+        let was_on = node.is_on();
+        node.set_requirements(requirements);
+        let is_on = node.is_on();
+        if self.is_initialised && was_on != is_on {
+            self.propagate_change(node_id, is_on);
+        }
+    }
+
+    /// remove the node and all the links toward and from it
+    /// the node must not be the source or the button of a storing block nor a node of a memory or
+    /// of a peripheral
+    pub fn remove_node(&mut self, node_id: NodeId) -> Result<(), Error> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(Error::NodeNotFound(node_id));
+        }
+        let is_read = self
+            .nodes
+            .keys()
+            .any(|id| id != node_id && self.get_read_nodes(id).contains(&node_id));
+        let is_in_memory = self.memories.iter().any(|memory| {
+            memory.address.contains(&node_id)
+                || memory.data_in.contains(&node_id)
+                || memory.write_enable == Some(node_id)
+        });
+        let is_displayed = self
+            .displays
+            .iter()
+            .any(|display| display.nodes.contains(&node_id));
+        let is_in_keyboard = self.keyboards.iter().any(|keyboard| {
+            keyboard.data.contains(&node_id)
+                || keyboard.strobe == node_id
+                || keyboard.acknowledge == Some(node_id)
+        });
+        let is_in_transmitter = self.transmitters.iter().any(|transmitter| {
+            transmitter.data.contains(&node_id) || transmitter.strobe == node_id
+        });
+        if is_read || is_in_memory || is_displayed || is_in_keyboard || is_in_transmitter {
+            return Err(Error::NodeInUse(node_id));
+        }
+
+        for parent in self.parents.remove(node_id).unwrap_or_default() {
+            if let Some(node) = self.get_mut_node(parent) {
+                node.get_mut_children().retain(|child| *child != node_id);
+            }
+        }
+        let is_on = self.is_on(node_id);
+        self.forced.remove(node_id);
+        self.unlinked_nodes.retain(|id| *id != node_id);
+        let node = self.nodes.remove(node_id).unwrap();
+        for child in node.get_children() {
+            if let Some(parents) = self.parents.get_mut(*child) {
+                parents.retain(|parent| *parent != node_id);
+            }
+        }
+        if self.is_initialised && is_on {
+            for child in node.get_children() {
+                self.push_action(NodeAction::DecreaseValue, *child);
+            }
+        }
+
+        self.actions_queue.retain(|(_, id, _)| *id != node_id);
+        self.timed_queue.retain(|action| action.0 .3 != node_id);
+        self.waves.remove_node(node_id);
+        self.waves.forget_ranks();
+        self.clocks.retain(|id| *id != node_id);
+        self.randoms.retain(|id| *id != node_id);
+        self.delays.remove(node_id);
+        self.names.retain(|_, id| *id != node_id);
+        self.breakpoints.remove(node_id);
+        self.remove_observers(node_id);
+        self.untrack_node(node_id);
+        Ok(())
+    }
+
+    /// replace the node by a new one, possibly of another kind
+    /// the links toward the node are kept while the links from it are the children of the new node
+    pub fn replace_node(&mut self, node_id: NodeId, mut node: Node) {
+        assert!(
+            !self.has_pending_actions(),
+            "can't replace a node while changes are pending"
+        );
+        if let Node::Clock(clock) = &mut node {
+            clock.is_on = clock.is_on_at(self.time);
+        }
+        let old_node = self.nodes.insert(node_id, node).expect("node not found");
+        self.waves.forget_ranks();
+        for child in old_node.get_children() {
+            let parents = self.parents.get_mut(*child).unwrap();
+            let position = parents
+                .iter()
+                .position(|parent| *parent == node_id)
+                .unwrap();
+            parents.remove(position);
+        }
+        for child in self.get_node(node_id).unwrap().get_children().clone() {
+            self.parents.get_or_insert_default(child).push(node_id);
+        }
+
+        self.clocks.retain(|id| *id != node_id);
+        self.randoms.retain(|id| *id != node_id);
+        match self.get_node(node_id).unwrap() {
+            Node::Clock(_) => {
+                assert!(
+                    self.get_parents(node_id).is_empty(),
+                    "a clock can't have parent nodes"
+                );
+                self.clocks.push(node_id);
+            }
+            Node::Input(_) => assert!(
+                self.get_parents(node_id).is_empty(),
+                "an input can't have parent nodes"
+            ),
+            Node::LogicBlock(_) => self.recompute_value(node_id),
+            Node::StoringBlock(_) => {}
+            Node::Memory(_) => {
+                self.read_memory_output(node_id);
+            }
+            Node::Random(node) => {
+                let is_clock_on = node.clock.is_some_and(|clock| self.is_on(clock));
+                self.randoms.push(node_id);
+                self.seed_random_node(node_id);
+                let Some(Node::Random(node)) = self.get_mut_node(node_id) else {
+                    unreachable!();
+                };
+                node.was_clock_on = is_clock_on;
+            }
+        }
+        self.link_read_nodes(node_id);
+        if !self.is_initialised {
+            return;
+        }
+
+        let was_on = self
+            .forced
+            .get(node_id)
+            .copied()
+            .unwrap_or(old_node.is_on());
+        let is_on = self.is_on(node_id);
+        if was_on {
+            for child in old_node.get_children() {
+                self.push_action(NodeAction::DecreaseValue, *child);
+            }
+        }
+        if is_on {
+            self.push_children_actions(NodeAction::IncreaseValue, node_id);
+        }
+        if was_on != is_on {
+            self.notify_change(node_id, is_on);
+        }
+        // the storing block takes the value of its source if its button is on, the edge triggered
+        // ones wait for the next edge
+        if matches!(self.get_node(node_id).unwrap(), Node::StoringBlock(_)) {
+            self.update_was_button_on(node_id);
+            self.update_storing_node_value(node_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+    use crate::{Error, Input, Keyboard, LogicBlock, Node, StoringBlock, Transmitter};
+
+    fn get_sum(map: &crate::Graph) -> u32 {
+        (17..=24).rev().fold(0, |sum, i| {
+            sum << 1 | map.get_node(i).unwrap().is_on() as u32
+        })
+    }
+
+    #[test]
+    fn test_remove_link() {
+        let mut map = init_map("./components/adder.pc");
+        // 3 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(2);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        assert!(get_sum(&map) == 4);
+
+        // the first carry doesn't ripple anymore
+        map.remove_link(25, 18).unwrap();
+        map.remove_link(25, 26).unwrap();
+        map.apply_changes();
+        assert!(get_sum(&map) == 2);
+        map.insert_links(vec![(25, 18), (25, 26)]);
+        map.apply_changes();
+        assert!(get_sum(&map) == 4);
+
+        // the first bit of the second input is now the second one
+        map.redirect_link(9, 17, 18).unwrap();
+        map.redirect_link(9, 25, 26).unwrap();
+        map.apply_changes();
+        assert!(get_sum(&map) == 5);
+    }
+
+    #[test]
+    fn test_remove_link_error() {
+        let mut map = init_map("./components/adder.pc");
+        assert!(map.remove_link(1, 18) == Err(Error::LinkNotFound(1, 18)));
+        assert!(map.remove_link(100, 18) == Err(Error::NodeNotFound(100)));
+        assert!(map.redirect_link(1, 17, 100) == Err(Error::NodeNotFound(100)));
+        assert!(map.get_node(1).unwrap().get_children().contains(&17));
+    }
+
+    #[test]
+    fn test_set_requirements() {
+        let mut map = init_map("./components/adder.pc");
+        // 1 + 0
+        map.turn_on_lamp(1);
+        map.apply_changes();
+        assert!(get_sum(&map) == 1);
+        // the first carry is an or instead of an and
+        map.set_requirements(25, 0b11110);
+        map.apply_changes();
+        assert!(get_sum(&map) == 3);
+        map.set_requirements(25, 0b01100);
+        map.apply_changes();
+        assert!(get_sum(&map) == 1);
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut map = init_map("./components/adder.pc");
+        // 3 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(2);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        map.set_node_name(25, "carry");
+        map.remove_node(25).unwrap();
+        map.apply_changes();
+        assert!(map.get_node(25).is_none());
+        assert!(map.get_node_id("carry").is_none());
+        assert!(!map.get_node(1).unwrap().get_children().contains(&25));
+        assert!(get_sum(&map) == 2);
+
+        // the removed node doesn't get the pending changes
+        map.turn_off_lamp(1);
+        map.remove_node(17).unwrap();
+        map.apply_changes();
+        assert!(map.get_node(18).unwrap().is_on());
+    }
+
+    #[test]
+    fn test_remove_node_error() {
+        let mut map = init_map("./components/saver.pc");
+        assert!(map.remove_node(9) == Err(Error::NodeInUse(9)));
+        assert!(map.remove_node(100) == Err(Error::NodeNotFound(100)));
+        assert!(map.get_node(9).is_some());
+
+        let mut map = init_map("./components/display.pc");
+        assert!(map.remove_node(2) == Err(Error::NodeInUse(2)));
+
+        // the node 1 is the data, 2 the strobe and 3 the acknowledge node of a receiver, 4 is the
+        // data and 5 the strobe of a transmitter
+        let mut map = crate::Graph::new();
+        map.insert_nodes(
+            (1..=5)
+                .map(|node_id| (Node::Input(Input::new(false, vec![])), node_id))
+                .collect(),
+        );
+        map.init_graph_state();
+        map.add_keyboard(Keyboard::new_receiver(vec![1], 2, 3));
+        map.add_transmitter(Transmitter::new(vec![4], 5));
+        for node_id in 1..=5 {
+            assert!(map.remove_node(node_id) == Err(Error::NodeInUse(node_id)));
+        }
+    }
+
+    #[test]
+    fn test_remove_changed_node() {
+        // the lamp 1 turns the node 2 on, both change and are removed before being applied
+        let mut map = crate::Graph::new();
+        map.insert_nodes(vec![
+            (Node::LogicBlock(LogicBlock::new(0b11111, vec![2])), 1),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![])), 2),
+        ]);
+        map.init_graph_state();
+        map.turn_off_lamp(1);
+        map.remove_node(2).unwrap();
+        map.remove_node(1).unwrap();
+        assert!(map.apply_changes() == crate::ChangeSet::default());
+    }
+
+    #[test]
+    fn test_replace_node() {
+        let mut map = init_map("./components/adder.pc");
+        // 1 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        assert!(get_sum(&map) == 2);
+
+        // the first carry becomes an or with no children
+        map.replace_node(25, Node::LogicBlock(LogicBlock::new(0b11110, vec![])));
+        map.apply_changes();
+        assert!(map.get_node(25).unwrap().is_on());
+        assert!(get_sum(&map) == 0);
+
+        // the first sum bit stores the first input bit each time the second input bit is on
+        map.replace_node(
+            17,
+            Node::StoringBlock(StoringBlock::new(false, 1, 9, vec![18, 26])),
+        );
+        map.apply_changes();
+        assert!(map.get_node(17).unwrap().is_on());
+        assert!(get_sum(&map) == 3);
+        map.turn_off_lamp(9);
+        map.turn_off_lamp(1);
+        map.apply_changes();
+        assert!(map.get_node(17).unwrap().is_on());
+    }
+}
@@ -0,0 +1,94 @@
+use super::Graph;
+use super::NodeId;
+
+impl Graph {
+    /// make the node show the given state to its children whatever its inputs are, the node
+    /// keeps computing its own state in the background
+    /// the changes it causes are applied with the other pending changes
+    pub fn force(&mut self, node_id: NodeId, is_on: bool) {
+        let was_on = self.is_on(node_id);
+        self.forced.insert(node_id, is_on);
+        if self.is_initialised && was_on != is_on {
+            self.propagate_output(node_id, is_on);
+        }
+    }
+
+    /// stop forcing the node, it shows its own state to its children again
+    pub fn release(&mut self, node_id: NodeId) {
+        let Some(was_on) = self.forced.remove(node_id) else {
+            return;
+        };
+        let is_on = self.is_on(node_id);
+        if self.is_initialised && was_on != is_on {
+            self.propagate_output(node_id, is_on);
+        }
+    }
+
+    /// release every forced node
+    pub fn release_all(&mut self) {
+        let forced = self.forced.keys().collect::<Vec<NodeId>>();
+        for node_id in forced {
+            self.release(node_id);
+        }
+    }
+
+    pub fn is_forced(&self, node_id: NodeId) -> bool {
+        self.forced.contains_key(node_id)
+    }
+
+    pub fn has_forced_nodes(&self) -> bool {
+        !self.forced.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+
+    #[test]
+    fn test_force_gate() {
+        let mut map = init_map("./components/adder.pc");
+        // 1 + 1 with the carry of the first bit forced off
+        map.force(25, false);
+        assert!(map.is_forced(25));
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        let changes = map.apply_changes();
+        assert!(changes.changed == [(1, true), (9, true)]);
+        assert!(!map.is_on(25));
+        // the block still counts its parents
+        assert!(map.get_node(25).unwrap().is_on());
+        assert!(!map.is_on(18));
+
+        map.release(25);
+        assert!(!map.is_forced(25));
+        let changes = map.apply_changes();
+        assert!(changes.changed == [(18, true), (25, true)]);
+        assert!(map.is_on(18));
+    }
+
+    #[test]
+    fn test_force_storing_block() {
+        let mut map = init_map("./components/saver.pc");
+        map.force(18, true);
+        map.apply_changes();
+        assert!(map.is_on(10));
+        assert!(!map.get_node(18).unwrap().is_on());
+
+        // saving 1 while forced doesn't change what the children see
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        map.turn_off_lamp(9);
+        map.apply_changes();
+        assert!(map.get_node(18).unwrap().is_on());
+        map.force(18, false);
+        map.apply_changes();
+        assert!(!map.is_on(10));
+
+        map.release_all();
+        map.apply_changes();
+        assert!(map.is_on(10));
+        assert!(map.get_logical_block(10).unwrap().get_value() == 1);
+    }
+}
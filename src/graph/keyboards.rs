@@ -0,0 +1,35 @@
+use super::ChangeSet;
+use super::Graph;
+use crate::Keyboard;
+use std::mem;
+
+impl Graph {
+    pub fn add_keyboard(&mut self, keyboard: Keyboard) {
+        let nodes = keyboard.data.iter().chain([&keyboard.strobe]);
+        for node_id in nodes.chain(&keyboard.acknowledge) {
+            assert!(self.nodes.contains_key(*node_id), "node not found");
+        }
+        self.keyboards.push(keyboard);
+    }
+
+    pub fn get_keyboards(&self) -> &[Keyboard] {
+        &self.keyboards
+    }
+
+    pub fn get_mut_keyboard(&mut self, keyboard: usize) -> Option<&mut Keyboard> {
+        self.keyboards.get_mut(keyboard)
+    }
+
+    /// send the next byte of every keyboard and receiver or turn its strobe off, see
+    /// `Keyboard::step`
+    /// returns the changes applied by all the keyboards, merged
+    pub fn update_keyboards(&mut self) -> ChangeSet {
+        let mut keyboards = mem::take(&mut self.keyboards);
+        let mut changes = ChangeSet::default();
+        for keyboard in &mut keyboards {
+            changes.merge(keyboard.step(self));
+        }
+        self.keyboards = keyboards;
+        changes
+    }
+}
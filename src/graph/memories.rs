@@ -0,0 +1,173 @@
+use super::Graph;
+use super::NodeId;
+use crate::Memory;
+use crate::Node;
+use std::fs;
+
+impl Graph {
+    /// add a memory, the memory outputs refer to it by the returned index
+    pub fn add_memory(&mut self, memory: Memory) -> usize {
+        self.memories.push(memory);
+        self.memories.len() - 1
+    }
+
+    pub fn get_memory(&self, memory: usize) -> Option<&Memory> {
+        self.memories.get(memory)
+    }
+
+    /// the address given by the current states of the address nodes of the memory
+    pub fn get_address(&self, memory: usize) -> usize {
+        let memory = self.get_memory(memory).expect("memory not found");
+        memory
+            .address
+            .iter()
+            .enumerate()
+            .fold(0, |address, (i, node_id)| {
+                address | (self.is_on(*node_id) as usize) << i
+            })
+    }
+
+    /// write the contents of the memory in a .hex file, it can be loaded back as a ROM
+    pub fn dump_memory(&self, memory: usize, path: &str) {
+        let memory = self.get_memory(memory).expect("memory not found");
+        fs::write(path, memory.to_hex())
+            .unwrap_or_else(|_| panic!("Failed to write the file: {}", path));
+    }
+
+    /// write the data in nodes of the memories whose write enable node is the node at their
+    /// current address, it is done when the node turns on before its children are updated, so the
+    /// memory outputs read the new word and the data in nodes depending on the write enable node
+    /// give their previous state
+    pub(super) fn write_memories(&mut self, node_id: NodeId, is_on: bool) {
+        if !is_on || !self.is_initialised {
+            return;
+        }
+        for memory in 0..self.memories.len() {
+            if self.memories[memory].write_enable != Some(node_id) {
+                continue;
+            }
+            let address = self.get_address(memory);
+            let word = self.memories[memory]
+                .data_in
+                .iter()
+                .enumerate()
+                .fold(0, |word, (i, node_id)| {
+                    word | (self.is_on(*node_id) as u64) << i
+                });
+            self.memories[memory].write(address, word);
+        }
+    }
+
+    /// set the state of the memory output to the bit of the word at the current address without
+    /// propagating it, returns whether it changed
+    pub(super) fn read_memory_output(&mut self, node_id: NodeId) -> bool {
+        let Some(Node::Memory(node)) = self.get_node(node_id) else {
+            panic!("the node {} is not a memory output", node_id);
+        };
+        let (memory, bit) = (node.memory, node.bit);
+        let word = self.memories[memory].read(self.get_address(memory));
+        let Some(Node::Memory(node)) = self.get_mut_node(node_id) else {
+            unreachable!();
+        };
+        let was_on = node.is_on;
+        node.is_on = word >> bit & 1 == 1;
+        was_on != node.is_on
+    }
+
+    /// read the memory output again after a change of an address node
+    pub(super) fn update_memory_output(&mut self, node_id: NodeId) {
+        if self.read_memory_output(node_id) {
+            let is_on = self.get_node(node_id).unwrap().is_on();
+            self.propagate_change(node_id, is_on);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+    use crate::Node;
+    use crate::SimulationMode;
+
+    fn read(map: &mut crate::Graph, address: usize) -> u64 {
+        map.set(1, address & 1 == 1).unwrap();
+        map.set(2, address & 2 == 2).unwrap();
+        map.apply_changes();
+        assert!(map.get_address(0) == address);
+        (3..=6).fold(0, |word, node_id| {
+            word | (map.is_on(node_id) as u64) << (node_id - 3)
+        })
+    }
+
+    #[test]
+    fn test_rom() {
+        for mode in [SimulationMode::ZeroDelay, SimulationMode::Coalesced] {
+            let mut map = init_map("./components/rom.pc");
+            map.set_simulation_mode(mode);
+            assert!(map.get_parents(3) == [1, 2]);
+            // the address is 2 at the start
+            assert!(map.is_on(5) && !map.is_on(3));
+            for address in [0, 3, 2, 1, 3, 0] {
+                assert!(read(&mut map, address) == address as u64 * address as u64);
+            }
+        }
+    }
+
+    /// write the word at the address of the RAM of ram.pc then read it
+    fn write(map: &mut crate::Graph, address: usize, word: u64) -> u64 {
+        map.set(1, address & 1 == 1).unwrap();
+        map.set(2, address & 2 == 2).unwrap();
+        map.set(3, word & 1 == 1).unwrap();
+        map.set(4, word & 2 == 2).unwrap();
+        map.apply_changes();
+        map.set(5, true).unwrap();
+        map.apply_changes();
+        map.set(5, false).unwrap();
+        map.apply_changes();
+        map.is_on(6) as u64 | (map.is_on(7) as u64) << 1
+    }
+
+    #[test]
+    fn test_ram() {
+        let mut map = init_map("./components/ram.pc");
+        assert!(map.get_parents(6) == [1, 2, 5]);
+        assert!(write(&mut map, 1, 2) == 2);
+        assert!(write(&mut map, 2, 3) == 3);
+        assert!(write(&mut map, 1, 1) == 1);
+        assert!(map.get_memory(0).unwrap().words == [0, 1, 3, 0]);
+
+        // the outputs follow the address
+        map.set(1, false).unwrap();
+        map.set(2, true).unwrap();
+        map.apply_changes();
+        assert!(map.is_on(6) && map.is_on(7));
+
+        let path = std::env::temp_dir().join("pomme_ram.hex");
+        map.dump_memory(0, path.to_str().unwrap());
+        let rom = crate::Memory::from_file(path.to_str().unwrap(), vec![]);
+        assert!(rom.words == [0, 1, 3, 0]);
+    }
+
+    #[test]
+    fn test_ram_data_in_hold() {
+        // the data in node 3 is the inverted write enable node 2, its previous state is written
+        let mut map = crate::Graph::new();
+        let memory = map.add_memory(crate::Memory::new_ram(1, vec![], vec![3], 2));
+        map.insert_nodes(vec![
+            (Node::Input(crate::Input::new(false, vec![3])), 2),
+            (Node::LogicBlock(crate::LogicBlock::new(0b00001, vec![])), 3),
+            (Node::Memory(crate::MemoryOutput::new(memory, 0, vec![])), 4),
+        ]);
+        map.init_graph_state();
+        map.set(2, true).unwrap();
+        map.apply_changes();
+        assert!(!map.is_on(3));
+        assert!(map.is_on(4));
+    }
+
+    #[test]
+    fn test_remove_address_node() {
+        let mut map = init_map("./components/rom.pc");
+        assert!(map.remove_node(1) == Err(crate::Error::NodeInUse(1)));
+    }
+}
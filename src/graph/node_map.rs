@@ -0,0 +1,112 @@
+use super::NodeId;
+
+/// a map from the node ids stored in a vector indexed by the id, so nothing is hashed when a
+/// node is looked up while the changes are propagated
+/// the ids are expected to be dense like the ones given by the compiler, the vector being as long
+/// as the biggest id
+#[derive(Debug)]
+pub(super) struct NodeMap<T> {
+    slots: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> Default for NodeMap<T> {
+    fn default() -> Self {
+        NodeMap {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> NodeMap<T> {
+    /* getters */
+    pub(super) fn get(&self, node_id: NodeId) -> Option<&T> {
+        self.slots.get(node_id as usize)?.as_ref()
+    }
+
+    pub(super) fn get_mut(&mut self, node_id: NodeId) -> Option<&mut T> {
+        self.slots.get_mut(node_id as usize)?.as_mut()
+    }
+
+    pub(super) fn contains_key(&self, node_id: NodeId) -> bool {
+        self.get(node_id).is_some()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the ids in the map, sorted
+    pub(super) fn keys(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.iter().map(|(node_id, _)| node_id)
+    }
+
+    /// the ids and values in the map, sorted by id
+    pub(super) fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(node_id, value)| Some((node_id as NodeId, value.as_ref()?)))
+    }
+
+    /* pub methods */
+    /// returns the previous value of the id
+    pub(super) fn insert(&mut self, node_id: NodeId, value: T) -> Option<T> {
+        let index = node_id as usize;
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        let old_value = self.slots[index].replace(value);
+        if old_value.is_none() {
+            self.len += 1;
+        }
+        old_value
+    }
+
+    pub(super) fn remove(&mut self, node_id: NodeId) -> Option<T> {
+        let old_value = self.slots.get_mut(node_id as usize)?.take();
+        if old_value.is_some() {
+            self.len -= 1;
+        }
+        while matches!(self.slots.last(), Some(None)) {
+            self.slots.pop();
+        }
+        old_value
+    }
+
+    pub(super) fn get_or_insert_default(&mut self, node_id: NodeId) -> &mut T
+    where
+        T: Default,
+    {
+        if !self.contains_key(node_id) {
+            self.insert(node_id, T::default());
+        }
+        self.get_mut(node_id).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_map() {
+        let mut map = NodeMap::default();
+        assert!(map.is_empty());
+        assert!(map.insert(5, 'a').is_none());
+        assert!(map.insert(2, 'b').is_none());
+        assert!(map.insert(5, 'c') == Some('a'));
+        assert!(map.get(5) == Some(&'c') && map.get(3).is_none() && map.get(100).is_none());
+        assert!(map.keys().collect::<Vec<NodeId>>() == [2, 5]);
+        *map.get_or_insert_default(3) = 'd';
+        assert!(map.iter().collect::<Vec<_>>() == [(2, &'b'), (3, &'d'), (5, &'c')]);
+
+        assert!(map.remove(5) == Some('c'));
+        assert!(map.remove(5).is_none());
+        assert!(map.slots.len() == 4);
+        map.remove(2);
+        map.remove(3);
+        assert!(map.is_empty());
+    }
+}
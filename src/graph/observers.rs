@@ -0,0 +1,115 @@
+use super::node_map::NodeMap;
+use super::Graph;
+use super::NodeId;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::mpsc;
+
+type Callback = Box<dyn FnMut(NodeId, bool)>;
+type Sender = mpsc::Sender<(NodeId, bool)>;
+
+/// the callbacks and channels notified when a node changes
+#[derive(Default)]
+pub(super) struct Observers {
+    callbacks: NodeMap<Vec<Callback>>,
+    // the nodes listened by the channel, all of them if `None`
+    senders: Vec<(Option<HashSet<NodeId>>, Sender)>,
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("callbacks", &self.callbacks.keys().collect::<Vec<NodeId>>())
+            .field("nb_senders", &self.senders.len())
+            .finish()
+    }
+}
+
+impl Graph {
+    /// call the callback with the node and its new state each time the node changes
+    pub fn on_change(&mut self, node_id: NodeId, callback: impl FnMut(NodeId, bool) + 'static) {
+        assert!(self.nodes.contains_key(node_id), "node not found");
+        self.observers
+            .callbacks
+            .get_or_insert_default(node_id)
+            .push(Box::new(callback));
+    }
+
+    /// get the changes of the given nodes, or of all the nodes if `None`, as they happen
+    /// the channel is dropped by the graph once the receiver is
+    pub fn subscribe(&mut self, nodes: Option<Vec<NodeId>>) -> mpsc::Receiver<(NodeId, bool)> {
+        let (sender, receiver) = mpsc::channel();
+        let nodes = nodes.map(|nodes| nodes.into_iter().collect());
+        self.observers.senders.push((nodes, sender));
+        receiver
+    }
+
+    /// remove all the callbacks of the node
+    pub fn remove_observers(&mut self, node_id: NodeId) {
+        self.observers.callbacks.remove(node_id);
+    }
+
+    pub(super) fn notify_observers(&mut self, node_id: NodeId, is_on: bool) {
+        if let Some(callbacks) = self.observers.callbacks.get_mut(node_id) {
+            for callback in callbacks {
+                callback(node_id, is_on);
+            }
+        }
+        self.observers.senders.retain(|(nodes, sender)| {
+            let is_listened = nodes.as_ref().is_none_or(|nodes| nodes.contains(&node_id));
+            !is_listened || sender.send((node_id, is_on)).is_ok()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_on_change() {
+        let mut map = init_map("./components/adder.pc");
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let carry_changes = changes.clone();
+        map.on_change(25, move |node_id, is_on| {
+            carry_changes.borrow_mut().push((node_id, is_on))
+        });
+
+        // 1 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        assert!(*changes.borrow() == vec![(25, true)]);
+        map.turn_off_lamp(1);
+        map.apply_changes();
+        assert!(*changes.borrow() == vec![(25, true), (25, false)]);
+
+        map.remove_observers(25);
+        map.turn_on_lamp(1);
+        map.apply_changes();
+        assert!(changes.borrow().len() == 2);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut map = init_map("./components/adder.pc");
+        let sum = map.subscribe(Some(vec![17, 18]));
+        let all = map.subscribe(None);
+
+        // 1 + 1
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        assert!(
+            sum.try_iter().collect::<Vec<(u32, bool)>>() == [(17, true), (17, false), (18, true)]
+        );
+        assert!(all.try_iter().count() == 6);
+
+        drop(all);
+        map.turn_off_lamp(9);
+        map.apply_changes();
+        assert!(sum.try_iter().collect::<Vec<(u32, bool)>>() == [(17, true), (18, false)]);
+    }
+}
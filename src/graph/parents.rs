@@ -0,0 +1,175 @@
+use super::Graph;
+use super::NodeId;
+use crate::Node;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+impl Graph {
+    /// the nodes having a link toward the node, once per link
+    pub fn get_parents(&self, node_id: NodeId) -> &[NodeId] {
+        self.parents.get(node_id).map_or(&[], |parents| parents)
+    }
+
+    /// the logic blocks with more than 4 parents, they panic if more than 4 of them are on
+    pub fn get_overloaded_nodes(&self) -> Vec<NodeId> {
+        self.parents
+            .iter()
+            .filter(|(node_id, parents)| {
+                parents.len() > 4 && matches!(self.get_node(*node_id), Some(Node::LogicBlock(_)))
+            })
+            .map(|(node_id, _)| node_id)
+            .collect()
+    }
+
+    /// every node with a path of links toward the given node, sorted
+    pub fn get_cone_of_influence(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut cone = HashSet::new();
+        let mut stack = vec![node_id];
+        while let Some(node_id) = stack.pop() {
+            for parent in self.get_parents(node_id) {
+                if cone.insert(*parent) {
+                    stack.push(*parent);
+                }
+            }
+        }
+        let mut cone = cone.into_iter().collect::<Vec<NodeId>>();
+        cone.sort();
+        cone
+    }
+
+    /// the logic blocks having parents grouped by level, the blocks of a level only depend on the
+    /// blocks of the previous levels and on the storing blocks, clocks and logic blocks without
+    /// parents, each level is sorted
+    /// returns `None` if the logic blocks make a loop
+    pub fn get_levels(&self) -> Option<Vec<Vec<NodeId>>> {
+        let (levels, is_complete) = self.get_acyclic_levels();
+        is_complete.then_some(levels)
+    }
+
+    /// the levels of the logic blocks which are neither in a loop nor after one, and whether all
+    /// the logic blocks having parents are in them
+    pub(crate) fn get_acyclic_levels(&self) -> (Vec<Vec<NodeId>>, bool) {
+        let is_gate = |node_id: NodeId| {
+            matches!(self.get_node(node_id), Some(Node::LogicBlock(_)))
+                && !self.get_parents(node_id).is_empty()
+        };
+        let mut nb_parents = HashMap::new();
+        for node_id in self.get_node_ids().into_iter().filter(|id| is_gate(*id)) {
+            let parents = self.get_parents(node_id);
+            nb_parents.insert(node_id, parents.iter().filter(|id| is_gate(**id)).count());
+        }
+        let mut level = nb_parents
+            .iter()
+            .filter(|(_, nb_parents)| **nb_parents == 0)
+            .map(|(node_id, _)| *node_id)
+            .collect::<Vec<NodeId>>();
+        let mut levels = Vec::new();
+        let mut nb_levelized_nodes = 0;
+        while !level.is_empty() {
+            level.sort();
+            let mut next_level = Vec::new();
+            for node_id in &level {
+                for child in self.get_node(*node_id).unwrap().get_children() {
+                    let Some(nb_parents) = nb_parents.get_mut(child) else {
+                        continue;
+                    };
+                    *nb_parents -= 1;
+                    if *nb_parents == 0 {
+                        next_level.push(*child);
+                    }
+                }
+            }
+            nb_levelized_nodes += level.len();
+            levels.push(level);
+            level = next_level;
+        }
+        (levels, nb_levelized_nodes == nb_parents.len())
+    }
+
+    /// set the value of the logic block to the number of its parents that are on
+    /// the changes of its parents that are still pending are counted twice
+    pub fn recompute_value(&mut self, node_id: NodeId) {
+        let value = self
+            .get_parents(node_id)
+            .iter()
+            .filter(|parent| self.is_on(**parent))
+            .count();
+        let node = self
+            .get_mut_logical_block(node_id)
+            .expect("only logic blocks have a value");
+        node.set_value(value as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+
+    #[test]
+    fn test_get_parents() {
+        let mut map = init_map("./components/adder.pc");
+        assert!(map.get_parents(1).is_empty());
+        assert!(map.get_parents(18) == [2, 10, 25]);
+        assert!(map.get_parents(26) == [2, 10, 25]);
+
+        map.remove_link(25, 18).unwrap();
+        assert!(map.get_parents(18) == [2, 10]);
+        map.insert_links(vec![(1, 18), (1, 18)]);
+        assert!(map.get_parents(18) == [2, 10, 1, 1]);
+        map.redirect_link(1, 18, 19).unwrap();
+        assert!(map.get_parents(18) == [2, 10, 1]);
+        assert!(map.get_parents(19).contains(&1));
+
+        map.remove_node(1).unwrap();
+        assert!(map.get_parents(18) == [2, 10]);
+        assert!(!map.get_parents(19).contains(&1));
+        assert!(map.get_overloaded_nodes().is_empty());
+    }
+
+    #[test]
+    fn test_overloaded_nodes() {
+        let mut map = init_map("./components/adder.pc");
+        map.insert_links(vec![(1, 18), (3, 18)]);
+        assert!(map.get_overloaded_nodes() == [18]);
+    }
+
+    #[test]
+    fn test_cone_of_influence() {
+        let map = init_map("./components/adder.pc");
+        assert!(map.get_cone_of_influence(1).is_empty());
+        assert!(map.get_cone_of_influence(17) == [1, 9]);
+        assert!(map.get_cone_of_influence(19) == [1, 2, 3, 9, 10, 11, 25, 26]);
+
+        let map = init_map("./components/saver.pc");
+        assert!(map.get_cone_of_influence(18) == [1, 9]);
+        assert!(map.get_cone_of_influence(10) == [1, 9, 18]);
+    }
+
+    #[test]
+    fn test_levels() {
+        let mut map = init_map("./components/adder.pc");
+        let levels = map.get_levels().unwrap();
+        assert!(levels.len() == 8);
+        assert!(levels[0] == [17, 25]);
+        assert!(levels[1] == [18, 26]);
+        assert!(levels[7] == [24, 32]);
+
+        let map_saver = init_map("./components/saver.pc");
+        assert!(map_saver.get_levels().unwrap() == [(10..=17).collect::<Vec<u32>>()]);
+
+        map.insert_links(vec![(26, 25)]);
+        assert!(map.get_levels().is_none());
+    }
+
+    #[test]
+    fn test_recompute_value() {
+        let mut map = init_map("./components/adder.pc");
+        map.turn_on_lamp(1);
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        let node = map.get_mut_logical_block(25).unwrap();
+        node.set_value(0);
+        map.recompute_value(25);
+        assert!(map.get_logical_block(25).unwrap().get_value() == 2);
+    }
+}
@@ -0,0 +1,169 @@
+use super::Graph;
+use super::NodeId;
+use crate::Node;
+
+impl Graph {
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// restart the generators of the random nodes from the seed, each node getting its own
+    /// sequence of states
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for node_id in self.randoms.clone() {
+            self.seed_random_node(node_id);
+        }
+    }
+
+    pub(super) fn seed_random_node(&mut self, node_id: NodeId) {
+        let seed = self.seed;
+        let Some(Node::Random(node)) = self.get_mut_node(node_id) else {
+            panic!("the node {} is not a random node", node_id);
+        };
+        node.seed(seed, node_id);
+    }
+
+    /// draw a new state for the random nodes without clock, `run_for` does it at every tick
+    /// the changes are applied with the other pending changes
+    pub fn draw_random_nodes(&mut self) {
+        if !self.is_initialised {
+            return;
+        }
+        for node_id in self.randoms.clone() {
+            let Some(Node::Random(node)) = self.get_mut_node(node_id) else {
+                unreachable!();
+            };
+            if node.clock.is_some() {
+                continue;
+            }
+            let was_on = node.is_on;
+            let is_on = node.draw();
+            if was_on != is_on {
+                self.propagate_change(node_id, is_on);
+            }
+        }
+    }
+
+    pub(super) fn has_unclocked_random_nodes(&self) -> bool {
+        self.randoms.iter().any(|node_id| {
+            matches!(self.get_node(*node_id), Some(Node::Random(node)) if node.clock.is_none())
+        })
+    }
+
+    /// draw a new state for the random node if its clock turned on since the last update
+    pub(super) fn update_random_node(&mut self, node_id: NodeId) {
+        let Some(Node::Random(node)) = self.get_node(node_id) else {
+            panic!("the node {} is not a random node", node_id);
+        };
+        let Some(clock) = node.clock else {
+            return;
+        };
+        let is_clock_on = self.is_on(clock);
+        let is_initialised = self.is_initialised;
+        let Some(Node::Random(node)) = self.get_mut_node(node_id) else {
+            unreachable!();
+        };
+        let is_rising_edge = is_clock_on && !node.was_clock_on;
+        node.was_clock_on = is_clock_on;
+        if !is_rising_edge || !is_initialised {
+            return;
+        }
+        let was_on = node.is_on;
+        let is_on = node.draw();
+        if was_on != is_on {
+            self.propagate_change(node_id, is_on);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::init_map;
+    use crate::Graph;
+    use crate::SimulationMode;
+
+    /// the states of the clocked random node 2 of random.pc on the rising edges of its clock
+    fn get_coin_flips(map: &mut Graph) -> Vec<bool> {
+        (0..32)
+            .map(|_| {
+                map.run_for(2);
+                assert!(map.is_on(4) == map.is_on(2));
+                map.is_on(2)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seed() {
+        let mut map = init_map("./components/random.pc");
+        assert!(map.get_seed() == 42);
+        let flips = get_coin_flips(&mut map);
+        assert!(flips.contains(&true) && flips.contains(&false));
+        // the same seed gives the same states whatever the simulation mode
+        for mode in [SimulationMode::ZeroDelay, SimulationMode::Coalesced] {
+            let mut map = init_map("./components/random.pc");
+            map.set_simulation_mode(mode);
+            assert!(get_coin_flips(&mut map) == flips);
+        }
+        let mut map = init_map("./components/random.pc");
+        map.set_seed(7);
+        assert!(get_coin_flips(&mut map) != flips);
+        map.set_seed(42);
+        assert!(get_coin_flips(&mut map) == flips);
+    }
+
+    #[test]
+    fn test_clock() {
+        // the clocked random node only changes on the rising edges of its clock
+        let mut map = init_map("./components/random.pc");
+        for _ in 0..32 {
+            let is_on = map.is_on(2);
+            map.run_for(1);
+            assert!(!map.is_on(1) && map.is_on(2) == is_on);
+            map.apply_changes();
+            assert!(map.is_on(2) == is_on);
+            map.run_for(1);
+        }
+    }
+
+    #[test]
+    fn test_unclocked() {
+        // the random node without clock draws a new state at each tick
+        let get_noise = |map: &mut Graph| {
+            (0..32)
+                .map(|_| {
+                    map.run_for(1);
+                    map.is_on(3)
+                })
+                .collect::<Vec<bool>>()
+        };
+        let noise = get_noise(&mut init_map("./components/random.pc"));
+        assert!(noise.contains(&true) && noise.contains(&false));
+        let mut map = init_map("./components/random.pc");
+        map.run_for(32);
+        assert!(map.is_on(3) == noise[31]);
+        // applying the changes, like the peripherals do, doesn't draw
+        let mut map = init_map("./components/random.pc");
+        map.apply_changes();
+        let is_on = map.is_on(3);
+        map.apply_changes();
+        assert!(map.is_on(3) == is_on);
+        assert!(get_noise(&mut map) == noise);
+        // an explicit draw is the next one of the sequence
+        map.draw_random_nodes();
+        map.apply_changes();
+        let mut other_map = init_map("./components/random.pc");
+        other_map.run_for(33);
+        assert!(map.is_on(3) == other_map.is_on(3));
+        // the nodes have their own sequences
+        let mut map = init_map("./components/random.pc");
+        assert!(get_coin_flips(&mut map) != noise);
+    }
+
+    #[test]
+    fn test_remove_clock() {
+        let mut map = init_map("./components/random.pc");
+        assert!(map.remove_node(1) == Err(crate::Error::NodeInUse(1)));
+    }
+}
@@ -0,0 +1,84 @@
+use super::Graph;
+use super::NodeId;
+use crate::Transmitter;
+use std::mem;
+
+impl Graph {
+    pub fn add_transmitter(&mut self, transmitter: Transmitter) {
+        for node_id in transmitter.data.iter().chain([&transmitter.strobe]) {
+            assert!(self.nodes.contains_key(*node_id), "node not found");
+        }
+        self.transmitters.push(transmitter);
+    }
+
+    pub fn get_transmitters(&self) -> &[Transmitter] {
+        &self.transmitters
+    }
+
+    /// note the transmitters whose strobe is the node when it turns on, they send their byte once
+    /// the changes of the wave are all applied, see `send_bytes`
+    pub(super) fn strobe_transmitters(&mut self, node_id: NodeId, is_on: bool) {
+        if !is_on || !self.is_initialised {
+            return;
+        }
+        for transmitter in 0..self.transmitters.len() {
+            if self.transmitters[transmitter].strobe == node_id {
+                self.strobed_transmitters.push(transmitter);
+            }
+        }
+    }
+
+    /// send the byte given by the data nodes of the transmitters strobed during the wave, so the
+    /// data nodes changing in the same wave as the strobe are read with their new state
+    /// in timed mode the wave is the tick of the strobe edge
+    pub(super) fn send_bytes(&mut self) {
+        for transmitter in mem::take(&mut self.strobed_transmitters) {
+            let byte = self.transmitters[transmitter]
+                .data
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, node_id)| {
+                    byte | (self.is_on(*node_id) as u8) << i
+                });
+            self.transmitters[transmitter].send(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Graph, Input, LogicBlock, Node, Transmitter};
+
+    #[test]
+    fn test_same_wave() {
+        // the input 1 is the data and 2 the strobe
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (Node::Input(Input::new(false, vec![])), 1),
+            (Node::Input(Input::new(false, vec![])), 2),
+        ]);
+        map.init_graph_state();
+        map.add_transmitter(Transmitter::new(vec![1], 2));
+        map.set(2, true).unwrap();
+        map.set(1, true).unwrap();
+        assert!(map.get_transmitters()[0].sent.is_empty());
+        map.apply_changes();
+        assert!(map.get_transmitters()[0].sent == [1]);
+
+        // the input 3 turns the strobe 4 on before the data 6, which is one block further
+        let mut map = Graph::new();
+        map.insert_nodes(vec![
+            (Node::Input(Input::new(false, vec![4, 5])), 3),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![])), 4),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![6])), 5),
+            (Node::LogicBlock(LogicBlock::new(0b00010, vec![])), 6),
+        ]);
+        map.init_graph_state();
+        map.add_transmitter(Transmitter::new(vec![6], 4));
+        map.set(3, true).unwrap();
+        map.step();
+        assert!(map.is_on(4) && !map.is_on(6));
+        map.apply_changes();
+        assert!(map.get_transmitters()[0].sent == [1]);
+    }
+}
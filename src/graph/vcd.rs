@@ -0,0 +1,192 @@
+use super::Graph;
+use super::NodeId;
+use super::SimulationMode;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+/// the transitions recorded since `start_recording`
+#[derive(Debug)]
+pub(super) struct Recorder {
+    nodes: Vec<NodeId>,
+    // the index of each recorded node in `nodes`
+    indexes: HashMap<NodeId, usize>,
+    start: u64,
+    initial_values: Vec<bool>,
+    // (timestamp, node, new state)
+    changes: Vec<(u64, NodeId, bool)>,
+}
+
+/// the vcd identifier of the nth recorded node, made of the printable ascii characters
+fn get_identifier(mut index: usize) -> String {
+    let mut identifier = String::new();
+    loop {
+        identifier.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            return identifier;
+        }
+        index -= 1;
+    }
+}
+
+impl Graph {
+    /// record every transition of the given nodes, or of all the nodes if `None`
+    /// the timestamps are the ticks in timed mode and the numbers of the waves of changes in zero
+    /// delay and coalesced modes, the initial values being dumped at the last wave applied so the
+    /// changes of the next one come after them
+    pub fn start_recording(&mut self, nodes: Option<Vec<NodeId>>) {
+        let nodes = nodes.unwrap_or_else(|| self.get_node_ids());
+        let initial_values = nodes.iter().map(|node_id| self.is_on(*node_id)).collect();
+        let start = match self.mode {
+            SimulationMode::ZeroDelay | SimulationMode::Coalesced => self.nb_waves,
+            SimulationMode::Timed => self.time,
+        };
+        self.recorder = Some(Recorder {
+            indexes: nodes
+                .iter()
+                .enumerate()
+                .map(|(i, node_id)| (*node_id, i))
+                .collect(),
+            nodes,
+            start,
+            initial_values,
+            changes: Vec::new(),
+        });
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// the recorded transitions as a value change dump
+    pub fn get_vcd(&self) -> String {
+        let recorder = self.recorder.as_ref().expect("the graph is not recording");
+        let mut names = vec![None; recorder.nodes.len()];
+        for (name, node_id) in &self.names {
+            if let Some(i) = recorder.indexes.get(node_id) {
+                names[*i] = Some(name.clone());
+            }
+        }
+
+        let mut content = String::new();
+        content.push_str("$timescale 1ns $end\n");
+        content.push_str("$scope module pomme $end\n");
+        for (i, node_id) in recorder.nodes.iter().enumerate() {
+            let name = names[i].clone().unwrap_or(format!("node_{}", node_id));
+            content.push_str(&format!(
+                "$var wire 1 {} {} $end\n",
+                get_identifier(i),
+                name
+            ));
+        }
+        content.push_str("$upscope $end\n");
+        content.push_str("$enddefinitions $end\n");
+
+        content.push_str(&format!("#{}\n$dumpvars\n", recorder.start));
+        for (i, is_on) in recorder.initial_values.iter().enumerate() {
+            content.push_str(&format!("{}{}\n", *is_on as u8, get_identifier(i)));
+        }
+        content.push_str("$end\n");
+
+        let mut last_timestamp = recorder.start;
+        for (timestamp, node_id, is_on) in &recorder.changes {
+            if *timestamp != last_timestamp {
+                content.push_str(&format!("#{}\n", timestamp));
+                last_timestamp = *timestamp;
+            }
+            let i = recorder.indexes[node_id];
+            content.push_str(&format!("{}{}\n", *is_on as u8, get_identifier(i)));
+        }
+        content
+    }
+
+    /// write the recorded transitions in a file viewable in GTKWave
+    pub fn write_vcd(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.get_vcd())
+    }
+
+    /// the tick or the number of the wave being applied
+    fn get_timestamp(&self) -> u64 {
+        match self.mode {
+            SimulationMode::ZeroDelay | SimulationMode::Coalesced => self.nb_waves + 1,
+            SimulationMode::Timed => self.time,
+        }
+    }
+
+    pub(super) fn record_change(&mut self, node_id: NodeId, is_on: bool) {
+        let timestamp = self.get_timestamp();
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if recorder.indexes.contains_key(&node_id) {
+            recorder.changes.push((timestamp, node_id, is_on));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+
+    #[test]
+    fn test_identifier() {
+        assert!(get_identifier(0) == "!");
+        assert!(get_identifier(93) == "~");
+        assert!(get_identifier(94) == "!!");
+        assert!(get_identifier(95) == "\"!");
+    }
+
+    #[test]
+    fn test_vcd() {
+        let mut map = init_map("./components/adder.pc");
+        map.set_node_name(25, "carry");
+        map.start_recording(Some(vec![1, 17, 25]));
+        map.turn_on_lamp(1);
+        map.apply_changes();
+        map.turn_on_lamp(9);
+        map.apply_changes();
+        assert!(
+            map.get_vcd()
+                == "$timescale 1ns $end
+$scope module pomme $end
+$var wire 1 ! node_1 $end
+$var wire 1 \" node_17 $end
+$var wire 1 # carry $end
+$upscope $end
+$enddefinitions $end
+#1
+$dumpvars
+0!
+0\"
+0#
+$end
+#2
+1!
+1\"
+#3
+0\"
+1#
+"
+        );
+    }
+
+    #[test]
+    fn test_timed_vcd() {
+        let mut map = init_map("./components/toggle.pc");
+        map.set_simulation_mode(SimulationMode::Timed);
+        map.start_recording(None);
+        map.run_for(5);
+        let vcd = map.get_vcd();
+        assert!(vcd.contains("$var wire 1 ! clock $end"));
+        assert!(vcd.contains("$var wire 1 & output $end"));
+        // the clock falls, the master samples and the slave samples one tick after the rise
+        assert!(vcd.ends_with("#2\n0!\n#3\n1\"\n#4\n1$\n1!\n#5\n1'\n0\"\n1%\n"));
+
+        let path = std::env::temp_dir().join("pomme_test_timed_vcd.vcd");
+        map.write_vcd(path.to_str().unwrap()).unwrap();
+        assert!(fs::read_to_string(&path).unwrap() == vcd);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,11 @@
+#[derive(Debug)]
+pub struct Input {
+    pub is_on: bool,
+    pub children: Vec<u32>,
+}
+
+impl Input {
+    pub fn new(is_on: bool, children: Vec<u32>) -> Input {
+        Input { is_on, children }
+    }
+}
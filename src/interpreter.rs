@@ -1,5 +1,10 @@
-use crate::{Graph, LogicBlock, Node, StoringBlock};
+use crate::{
+    Clock, Display, DisplayKind, Graph, Input, Keyboard, LogicBlock, Memory, MemoryOutput, Node,
+    Random, StoringBlock, Transmitter, Trigger,
+};
 use std::fs;
+use std::io;
+use std::path::Path;
 
 fn get_logical_block_from_line(line: &str, i: usize) -> (Node, u32) {
     let parameters = line.split_whitespace().collect::<Vec<&str>>();
@@ -8,64 +13,395 @@ fn get_logical_block_from_line(line: &str, i: usize) -> (Node, u32) {
         "line {i} is not valid:\n{line}\ndoesn't have enough parameters'"
     );
 
-    let node_id = parameters[0].parse::<u32>().expect(&format!(
-        "line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32)"
-    ));
+    let node_id = parameters[0].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32)")
+    });
 
-    let requirements = u8::from_str_radix(parameters[1], 2).expect(&format!(
-                "line {i} is not valid:\n{line}\nexpected the second parameter to be a binary number with only 1 and 0"
-    ));
+    let requirements = u8::from_str_radix(parameters[1], 2).unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be a binary number with only 1 and 0"));
 
     let children = parameters
         .iter()
         .skip(2)
-        .map(|x| x.parse::<u32>().expect(&format!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
         .collect();
 
     let node = Node::LogicBlock(LogicBlock::new(requirements, children));
     (node, node_id)
 }
 
-fn get_storing_block_from_line(line: &str, i: usize) -> (Node, u32) {
+fn get_storing_block_from_line(line: &str, i: usize, trigger: Trigger) -> (Node, u32) {
     let parameters = line.split_whitespace().collect::<Vec<&str>>();
     assert!(parameters.len() >= 3, "line {i} is not valid:\n{line}");
 
-    let node_id = parameters[0][1..].parse::<u32>().expect(&format!(
-        "line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first ^)"
-    ));
+    let node_id = parameters[0][1..].parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first ^, / or \\)"));
 
-    let button = parameters[1].parse::<u32>().expect(&format!(
-        "line {i} is not valid:\n{line}\nexpected the first second to be an int (u32)"
-    ));
+    let button = parameters[1].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the first second to be an int (u32)")
+    });
+
+    let source = parameters[2].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the third parameter to be an int (u32)")
+    });
+
+    let children = parameters
+        .iter()
+        .skip(3)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .collect();
 
-    let source = parameters[2].parse::<u32>().expect(&format!(
-        "line {i} is not valid:\n{line}\nexpected the third parameter to be an int (u32)"
+    let node = Node::StoringBlock(StoringBlock::new_with_trigger(
+        false, source, button, children, trigger,
     ));
+    (node, node_id)
+}
+
+fn get_clock_from_line(line: &str, i: usize) -> (Node, u32) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 3, "line {i} is not valid:\n{line}");
+
+    let node_id = parameters[0][1..].parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first ~)"));
+
+    let period = parameters[1].parse::<u64>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be an int (u64)")
+    });
+
+    let phase = parameters[2].parse::<u64>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the third parameter to be an int (u64)")
+    });
+    assert!(
+        period >= 2 && phase < period,
+        "line {i} is not valid:\n{line}\nexpected a period of at least 2 and a phase smaller than the period"
+    );
 
     let children = parameters
         .iter()
         .skip(3)
-        .map(|x| x.parse::<u32>().expect(&format!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
         .collect();
 
-    let node = Node::StoringBlock(StoringBlock::new(false, source, button, children));
+    let node = Node::Clock(Clock::new(period, phase, children));
     (node, node_id)
 }
 
+fn get_input_from_line(line: &str, i: usize) -> (Node, u32) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 2, "line {i} is not valid:\n{line}");
+
+    let node_id = parameters[0][1..].parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first >)"));
+
+    let is_on = match parameters[1] {
+        "0" => false,
+        "1" => true,
+        _ => panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be 0 or 1"),
+    };
+
+    let children = parameters
+        .iter()
+        .skip(2)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .collect();
+
+    let node = Node::Input(Input::new(is_on, children));
+    (node, node_id)
+}
+
+/// the memory number and the memory, the path of its file being relative to the directory of the
+/// .pc file
+fn get_memory_from_line(line: &str, i: usize, directory: &Path) -> (usize, Memory) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 2, "line {i} is not valid:\n{line}");
+
+    let memory = parameters[0][1..].parse::<usize>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (usize) (after the first $)"));
+
+    let path = directory.join(parameters[1]);
+
+    let address = parameters
+        .iter()
+        .skip(2)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the address node parameters to be int (u32)")))
+        .collect();
+
+    (memory, Memory::from_file(path.to_str().unwrap(), address))
+}
+
+/// the memory number and the RAM, the address and data in nodes are comma separated lists
+/// the lines of the disks have the path of their file after the memory number, relative to the
+/// directory of the .pc file
+fn get_ram_from_line(line: &str, i: usize, directory: &Path) -> (usize, Memory) {
+    let mut parameters = line.split_whitespace().collect::<Vec<&str>>();
+    let path = match line.starts_with('&') && parameters.len() > 1 {
+        true => Some(directory.join(parameters.remove(1))),
+        false => None,
+    };
+    assert!(parameters.len() == 5, "line {i} is not valid:\n{line}");
+
+    let memory = parameters[0][1..].parse::<usize>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (usize) (after the first % or &)"));
+
+    let depth = parameters[1].parse::<usize>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be an int (usize)")
+    });
+
+    let write_enable = parameters[2].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the third parameter to be an int (u32)")
+    });
+
+    let get_nodes = |parameter: &str| {
+        parameter
+            .split(',')
+            .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the address and data in nodes to be comma separated int (u32)")))
+            .collect::<Vec<u32>>()
+    };
+
+    let (address, data_in) = (get_nodes(parameters[3]), get_nodes(parameters[4]));
+    let ram = match path {
+        Some(path) => Memory::new_disk(
+            path.to_str().unwrap(),
+            depth,
+            address,
+            data_in,
+            write_enable,
+        ),
+        None => Memory::new_ram(depth, address, data_in, write_enable),
+    };
+    (memory, ram)
+}
+
+/// the clock is `-` for a random node drawing a new state at each tick
+fn get_random_from_line(line: &str, i: usize) -> (Node, u32) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 2, "line {i} is not valid:\n{line}");
+
+    let node_id = parameters[0][1..].parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first +)"));
+
+    let clock = match parameters[1] {
+        "-" => None,
+        clock => Some(clock.parse::<u32>().unwrap_or_else(|_| {
+            panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be an int (u32) or -")
+        })),
+    };
+
+    let children = parameters
+        .iter()
+        .skip(2)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .collect();
+
+    let node = Node::Random(Random::new(clock, children));
+    (node, node_id)
+}
+
+fn get_memory_output_from_line(line: &str, i: usize) -> (Node, u32) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 3, "line {i} is not valid:\n{line}");
+
+    let node_id = parameters[0][1..].parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected the first parameter to be an int (u32) (after the first #)"));
+
+    let memory = parameters[1].parse::<usize>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be an int (usize)")
+    });
+
+    let bit = parameters[2].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the third parameter to be an int (u32)")
+    });
+
+    let children = parameters
+        .iter()
+        .skip(3)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the child node parameters to be int (u32)")))
+        .collect();
+
+    let node = Node::Memory(MemoryOutput::new(memory, bit, children));
+    (node, node_id)
+}
+
+/// the kind is `segments`, `leds` or `pixels:WIDTHxHEIGHT`
+fn get_display_from_line(line: &str, i: usize) -> Display {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() >= 3, "line {i} is not valid:\n{line}");
+
+    let kind = match parameters[1].split_once(':') {
+        None if parameters[1] == "segments" => DisplayKind::SevenSegment,
+        None if parameters[1] == "leds" => DisplayKind::LedBar,
+        Some(("pixels", size)) => {
+            let (width, height) = size
+                .split_once('x')
+                .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                .unwrap_or_else(|| panic!("line {i} is not valid:\n{line}\nexpected the size of the pixels to be WIDTHxHEIGHT"));
+            DisplayKind::Pixels { width, height }
+        }
+        _ => panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be segments, leds or pixels:WIDTHxHEIGHT"),
+    };
+
+    let nodes = parameters
+        .iter()
+        .skip(2)
+        .map(|x| x.parse::<u32>().unwrap_or_else(|_| panic!("line {i} is not valid:\n{line}\nexpected all the node parameters to be int (u32)")))
+        .collect();
+
+    Display::new(&parameters[0][1..], kind, nodes)
+}
+
+/// the strobe of the keyboard then its data nodes, the least significant first
+fn get_keyboard_from_line(line: &str, i: usize) -> Keyboard {
+    let parameters = line[1..]
+        .split_whitespace()
+        .map(|x| {
+            x.parse::<u32>().unwrap_or_else(|_| {
+                panic!("line {i} is not valid:\n{line}\nexpected all parameters to be int (u32)")
+            })
+        })
+        .collect::<Vec<u32>>();
+    assert!(
+        !parameters.is_empty() && parameters.len() <= 9,
+        "line {i} is not valid:\n{line}\nexpected a strobe and at most 8 data nodes"
+    );
+
+    Keyboard::new(parameters[1..].to_vec(), parameters[0])
+}
+
+/// the file the bytes are written to (`-` for the standard output), the strobe then the data
+/// nodes, the least significant first
+fn get_transmitter_from_line(line: &str, i: usize, directory: &Path) -> Transmitter {
+    let parameters = line[1..].split_whitespace().collect::<Vec<&str>>();
+    assert!(
+        parameters.len() >= 2 && parameters.len() <= 10,
+        "line {i} is not valid:\n{line}\nexpected a file, a strobe and at most 8 data nodes"
+    );
+
+    let nodes = parameters[1..]
+        .iter()
+        .map(|x| {
+            x.parse::<u32>().unwrap_or_else(|_| {
+                panic!("line {i} is not valid:\n{line}\nexpected all the node parameters to be int (u32)")
+            })
+        })
+        .collect::<Vec<u32>>();
+
+    let (data, strobe) = (nodes[1..].to_vec(), nodes[0]);
+    if parameters[0] == "-" {
+        return Transmitter::new_writer(data, strobe, io::stdout());
+    }
+    Transmitter::new_file(data, strobe, directory.join(parameters[0]))
+}
+
+/// the file whose bytes are received, the strobe, the acknowledge node then the data nodes, the
+/// least significant first
+fn get_receiver_from_line(line: &str, i: usize, directory: &Path) -> Keyboard {
+    let parameters = line[1..].split_whitespace().collect::<Vec<&str>>();
+    assert!(
+        parameters.len() >= 3 && parameters.len() <= 11,
+        "line {i} is not valid:\n{line}\nexpected a file, a strobe, an acknowledge node and at most 8 data nodes"
+    );
+
+    let nodes = parameters[1..]
+        .iter()
+        .map(|x| {
+            x.parse::<u32>().unwrap_or_else(|_| {
+                panic!("line {i} is not valid:\n{line}\nexpected all the node parameters to be int (u32)")
+            })
+        })
+        .collect::<Vec<u32>>();
+
+    let path = directory.join(parameters[0]);
+    let bytes =
+        fs::read(&path).unwrap_or_else(|_| panic!("Failed to read the file: {}", path.display()));
+    let mut receiver = Keyboard::new_receiver(nodes[2..].to_vec(), nodes[0], nodes[1]);
+    receiver.push(&bytes);
+    receiver
+}
+
+fn get_name_from_line(line: &str, i: usize) -> (String, u32) {
+    let parameters = line.split_whitespace().collect::<Vec<&str>>();
+    assert!(parameters.len() == 2, "line {i} is not valid:\n{line}");
+
+    let node_id = parameters[1].parse::<u32>().unwrap_or_else(|_| {
+        panic!("line {i} is not valid:\n{line}\nexpected the second parameter to be an int (u32)")
+    });
+
+    (parameters[0][1..].to_string(), node_id)
+}
+
 pub fn init_map(path: &str) -> Graph {
-    let contents = fs::read_to_string(path).expect(&format!("Failed to read the file: {}", path));
+    let contents =
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to read the file: {}", path));
     let lines = contents.split("\n").filter(|x| x != &"");
+    let directory = Path::new(path).parent().unwrap_or(Path::new("."));
     let mut nodes = Vec::new();
+    let mut names = Vec::new();
+    let mut memories = Vec::new();
+    let mut displays = Vec::new();
+    let mut keyboards = Vec::new();
+    let mut transmitters = Vec::new();
+    let mut seed = 0;
     for (i, line) in lines.enumerate() {
-        let is_logical_block = &line[0..=0] != "^";
-        let node = match is_logical_block {
-            true => get_logical_block_from_line(line, i),
-            false => get_storing_block_from_line(line, i),
+        if let Some(parameter) = line.strip_prefix("seed ") {
+            seed = parameter.trim().parse::<u64>().unwrap_or_else(|_| {
+                panic!("line {i} is not valid:\n{line}\nexpected the seed to be an int (u64)")
+            });
+            continue;
+        }
+        if line.starts_with('@') {
+            names.push(get_name_from_line(line, i));
+            continue;
+        }
+        if line.starts_with('$') {
+            memories.push(get_memory_from_line(line, i, directory));
+            continue;
+        }
+        if line.starts_with('%') || line.starts_with('&') {
+            memories.push(get_ram_from_line(line, i, directory));
+            continue;
+        }
+        if line.starts_with('*') {
+            displays.push(get_display_from_line(line, i));
+            continue;
+        }
+        if line.starts_with('?') {
+            keyboards.push(get_keyboard_from_line(line, i));
+            continue;
+        }
+        if line.starts_with('<') {
+            keyboards.push(get_receiver_from_line(line, i, directory));
+            continue;
+        }
+        if line.starts_with('!') {
+            transmitters.push(get_transmitter_from_line(line, i, directory));
+            continue;
+        }
+        let node = match &line[0..=0] {
+            "^" => get_storing_block_from_line(line, i, Trigger::Level),
+            "/" => get_storing_block_from_line(line, i, Trigger::RisingEdge),
+            "\\" => get_storing_block_from_line(line, i, Trigger::FallingEdge),
+            "~" => get_clock_from_line(line, i),
+            ">" => get_input_from_line(line, i),
+            "#" => get_memory_output_from_line(line, i),
+            "+" => get_random_from_line(line, i),
+            _ => get_logical_block_from_line(line, i),
         };
         nodes.push(node);
     }
     let mut graph = Graph::new();
+    memories.sort_by_key(|(memory, _)| *memory);
+    for (memory, content) in memories {
+        assert!(
+            graph.add_memory(content) == memory,
+            "the memories must be numbered from 0 without gaps"
+        );
+    }
     graph.insert_nodes(nodes);
+    graph.set_seed(seed);
+    for (name, node_id) in names {
+        graph.set_node_name(node_id, &name);
+    }
+    for display in displays {
+        graph.add_display(display);
+    }
+    for keyboard in keyboards {
+        graph.add_keyboard(keyboard);
+    }
+    for transmitter in transmitters {
+        graph.add_transmitter(transmitter);
+    }
     graph.init_graph_state();
     graph
 }
@@ -73,6 +409,7 @@ pub fn init_map(path: &str) -> Graph {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
 
     #[test]
     fn test_adder() {
@@ -153,4 +490,100 @@ mod tests {
         assert!(!map.get_node(16).unwrap().is_on());
         assert!(!map.get_node(17).unwrap().is_on());
     }
+
+    #[test]
+    fn test_inputs() {
+        let mut map = init_map("./components/input.pc");
+        let a = map.get_node_id("a").unwrap();
+        let b = map.get_node_id("b").unwrap();
+        let and = map.get_node_id("and").unwrap();
+        assert!(!map.get_node(a).unwrap().is_on());
+        assert!(map.get_node(b).unwrap().is_on());
+        assert!(!map.get_node(and).unwrap().is_on());
+
+        map.set(a, true).unwrap();
+        // setting an input to its state does nothing
+        map.set(a, true).unwrap();
+        map.apply_changes();
+        assert!(map.get_node(and).unwrap().is_on());
+        assert!(map.get_logical_block(and).unwrap().get_value() == 2);
+        assert!(map.toggle(b) == Ok(false));
+        map.apply_changes();
+        assert!(!map.get_node(and).unwrap().is_on());
+
+        assert!(map.set(and, true) == Err(Error::NotAnInput(and)));
+        assert!(map.toggle(42) == Err(Error::NodeNotFound(42)));
+    }
+
+    #[test]
+    fn test_disk() {
+        // the disk keeps its words when the graph is loaded again
+        let directory = std::env::temp_dir().join("pomme_disk");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("disk.pc");
+        fs::write(&path, ">1 0\n>2 0\n>3 0\n&0 disk.bin 2 3 1 2\n#4 0 0\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut map = init_map(path);
+        for (node_id, is_on) in [(1, true), (2, true), (3, true), (3, false)] {
+            map.set(node_id, is_on).unwrap();
+            map.apply_changes();
+        }
+        assert!(map.is_on(4));
+        drop(map);
+        assert!(fs::read(directory.join("disk.bin")).unwrap() == [0, 1]);
+
+        let mut map = init_map(path);
+        assert!(!map.is_on(4));
+        map.set(1, true).unwrap();
+        map.apply_changes();
+        assert!(map.is_on(4));
+    }
+
+    #[test]
+    fn test_serial() {
+        // the acknowledge node 10 follows the strobe 9 and is the strobe of the transmitter, so
+        // the bytes received are sent back
+        let directory = std::env::temp_dir().join("pomme_serial");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("in.txt"), "pomme\n").unwrap();
+        let mut contents = (1..=8)
+            .map(|node_id| format!(">{node_id} 0\n"))
+            .collect::<String>();
+        contents.push_str(">9 0 10\n10 00010\n");
+        contents.push_str("<in.txt 9 10 1 2 3 4 5 6 7 8\n!out.txt 10 1 2 3 4 5 6 7 8\n");
+        let path = directory.join("echo.pc");
+        fs::write(&path, contents).unwrap();
+
+        let mut map = init_map(path.to_str().unwrap());
+        assert!(map.get_keyboards()[0].acknowledge == Some(10));
+        while map.get_keyboards()[0].has_pending_bytes() || map.is_on(9) {
+            map.update_keyboards();
+        }
+        assert!(fs::read(directory.join("out.txt")).unwrap() == b"pomme\n");
+    }
+
+    #[test]
+    fn test_toggle() {
+        // master-slave storing blocks toggling the output on each rising edge of the clock 1
+        let mut map = init_map("./components/toggle.pc");
+        assert!(map.get_node_id("clock") == Some(1));
+        assert!(map.get_node_id("output") == Some(6));
+        assert!(map.get_node(1).unwrap().is_on());
+        assert!(!map.get_node(6).unwrap().is_on());
+
+        map.run_cycles(1, 1);
+        assert!(map.get_time() == 4);
+        assert!(map.get_node(6).unwrap().is_on());
+        map.run_for(2);
+        assert!(!map.get_node(1).unwrap().is_on());
+        assert!(map.get_node(6).unwrap().is_on());
+        map.run_for(2);
+        assert!(!map.get_node(6).unwrap().is_on());
+
+        map.run_cycles(1, 3);
+        assert!(map.get_node(6).unwrap().is_on());
+    }
 }
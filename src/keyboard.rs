@@ -0,0 +1,181 @@
+use crate::ChangeSet;
+use crate::Graph;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::mpsc;
+use std::thread;
+
+type NodeId = u32;
+
+/// feeds bytes to the graph through input nodes, the bits of the byte being set on the data nodes
+/// before the strobe turns on for one step to tell that a key is available
+/// a receiver is a keyboard waiting for the graph to acknowledge each byte: the strobe stays on
+/// until the acknowledge node turns on, and the next byte is only sent once it is off again
+#[derive(Debug)]
+pub struct Keyboard {
+    pub data: Vec<NodeId>, // the least significant first
+    pub strobe: NodeId,
+    pub acknowledge: Option<NodeId>,
+    pending: VecDeque<u8>,
+    receiver: Option<mpsc::Receiver<u8>>,
+}
+
+impl Keyboard {
+    pub fn new(data: Vec<NodeId>, strobe: NodeId) -> Keyboard {
+        assert!(data.len() <= 8, "a keyboard has at most 8 data nodes");
+        Keyboard {
+            data,
+            strobe,
+            acknowledge: None,
+            pending: VecDeque::new(),
+            receiver: None,
+        }
+    }
+
+    pub fn new_receiver(data: Vec<NodeId>, strobe: NodeId, acknowledge: NodeId) -> Keyboard {
+        let mut keyboard = Keyboard::new(data, strobe);
+        keyboard.acknowledge = Some(acknowledge);
+        keyboard
+    }
+
+    /* getters */
+    pub fn has_pending_bytes(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /* pub methods */
+    /// queue bytes to send to the graph
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.pending.extend(bytes);
+    }
+
+    /// queue the bytes read from the reader (like the standard input) as they come, the reader is
+    /// read by another thread
+    pub fn read_from(&mut self, mut reader: impl Read + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut byte = [0];
+            while reader.read_exact(&mut byte).is_ok() && sender.send(byte[0]).is_ok() {}
+        });
+        self.receiver = Some(receiver);
+    }
+
+    /// turn the strobe off if it is on, otherwise set the data nodes to the next byte and then
+    /// turn the strobe on, the changes are applied so the data is stable when the strobe changes
+    /// a receiver does nothing while the acknowledge node differs from the strobe
+    /// returns the changes applied by the step, merged
+    pub fn step(&mut self, graph: &mut Graph) -> ChangeSet {
+        if let Some(receiver) = &self.receiver {
+            self.pending.extend(receiver.try_iter());
+        }
+        let is_on = graph.is_on(self.strobe);
+        if self
+            .acknowledge
+            .is_some_and(|node_id| graph.is_on(node_id) != is_on)
+        {
+            return ChangeSet::default();
+        }
+        if is_on {
+            graph
+                .set(self.strobe, false)
+                .expect("the strobe of a keyboard must be an input");
+            return graph.apply_changes();
+        }
+        let Some(byte) = self.pending.pop_front() else {
+            return ChangeSet::default();
+        };
+        for (i, node_id) in self.data.iter().enumerate() {
+            graph
+                .set(*node_id, byte >> i & 1 == 1)
+                .expect("the data nodes of a keyboard must be inputs");
+        }
+        let mut changes = graph.apply_changes();
+        graph
+            .set(self.strobe, true)
+            .expect("the strobe of a keyboard must be an input");
+        changes.merge(graph.apply_changes());
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+    use crate::Input;
+    use crate::Node;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    fn get_key(map: &Graph) -> u8 {
+        (10..18).fold(0, |key, node_id| {
+            key | (map.is_on(node_id) as u8) << (node_id - 10)
+        })
+    }
+
+    #[test]
+    fn test_keyboard() {
+        let mut map = init_map("./components/keyboard.pc");
+        assert!(map.get_keyboards()[0].strobe == 9);
+        map.get_mut_keyboard(0).unwrap().push(b"pm");
+        let changes = map.update_keyboards();
+        assert!(map.is_on(9));
+        assert!(get_key(&map) == b'p');
+        let expected = [5, 6, 7, 9, 14, 15, 16].map(|node_id| (node_id, true));
+        assert!(changes.changed == expected);
+        assert!(changes.nb_events == 11);
+        assert!(map.update_keyboards().changed == [(9, false)]);
+        assert!(!map.is_on(9));
+        map.update_keyboards();
+        assert!(get_key(&map) == b'm');
+        assert!(!map.get_keyboards()[0].has_pending_bytes());
+        map.update_keyboards();
+        map.update_keyboards();
+        assert!(!map.is_on(9));
+        assert!(get_key(&map) == b'm');
+    }
+
+    #[test]
+    fn test_receiver() {
+        // the node 1 is the data, 2 the strobe and 3 the acknowledge node
+        let mut map = Graph::new();
+        map.insert_nodes(
+            (1..=3)
+                .map(|node_id| (Node::Input(Input::new(false, vec![])), node_id))
+                .collect(),
+        );
+        map.init_graph_state();
+        map.add_keyboard(Keyboard::new_receiver(vec![1], 2, 3));
+        map.get_mut_keyboard(0).unwrap().push(&[1, 0]);
+        map.update_keyboards();
+        assert!(map.is_on(1) && map.is_on(2));
+        // the strobe stays on until the byte is acknowledged
+        map.update_keyboards();
+        assert!(map.is_on(2));
+        map.set(3, true).unwrap();
+        map.update_keyboards();
+        assert!(!map.is_on(2));
+        // the next byte waits for the acknowledge node to turn off
+        map.update_keyboards();
+        assert!(!map.is_on(2));
+        map.set(3, false).unwrap();
+        map.update_keyboards();
+        assert!(!map.is_on(1) && map.is_on(2));
+    }
+
+    #[test]
+    fn test_read_from() {
+        let mut map = init_map("./components/keyboard.pc");
+        let mut keyboard = Keyboard::new((1..=8).collect(), 9);
+        keyboard.read_from(&b"e"[..]);
+        // the byte may take some time to come from the other thread
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while get_key(&map) != b'e' {
+            assert!(
+                Instant::now() < deadline,
+                "the byte never reached the keyboard"
+            );
+            keyboard.step(&mut map);
+        }
+    }
+}
@@ -1,13 +1,49 @@
+mod bit_parallel;
+mod clock;
+mod codegen;
 mod compiler;
+mod dense_graph;
+mod display;
+mod error;
 mod graph;
+mod input;
 mod interpreter;
+mod keyboard;
 mod logic_block;
+mod memory;
 mod node;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod random;
 mod storing_block;
+mod transmitter;
 
+pub use bit_parallel::BitParallelGraph;
+pub use bit_parallel::LANES;
+pub use clock::Clock;
+pub use codegen::generate_rust;
+pub use codegen::get_rust_source;
 pub use compiler::compile;
+pub use dense_graph::DenseGraph;
+pub use display::run_displays;
+pub use display::Display;
+pub use display::DisplayKind;
+pub use error::Error;
+pub use graph::Breakpoint;
+pub use graph::ChangeSet;
 pub use graph::Graph;
+pub use graph::NodeAction;
+pub use graph::SimulationMode;
+pub use input::Input;
 pub use interpreter::init_map;
+pub use keyboard::Keyboard;
 pub use logic_block::LogicBlock;
+pub use memory::Memory;
+pub use memory::MemoryOutput;
 pub use node::Node;
+#[cfg(feature = "parallel")]
+pub use parallel::ParallelGraph;
+pub use random::Random;
 pub use storing_block::StoringBlock;
+pub use storing_block::Trigger;
+pub use transmitter::Transmitter;
@@ -39,6 +39,7 @@ impl LogicBlock {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_comparison)]
 mod tests {
     use super::*;
 
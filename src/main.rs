@@ -1,9 +1,56 @@
+use pomme::compile;
+use pomme::generate_rust;
+use pomme::init_map;
+use pomme::run_displays;
 use pomme::Graph;
 use pomme::LogicBlock;
 use pomme::Node;
 use pomme::StoringBlock;
+use std::env;
+use std::io;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+    pomme compile <file.pomme>  compile the file into a .pc file next to it
+    pomme gen-rust <file.pc>    generate a rust module evaluating the file next to it
+    pomme run <file.pc> <ticks> [ms per tick]
+                                run the file and show its displays in the terminal, the
+                                standard input is sent to its first keyboard, the
+                                terminal only sends a line when enter is pressed";
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    match args.iter().map(|x| x.as_str()).collect::<Vec<&str>>()[..] {
+        [] => example(),
+        ["compile", path] => compile(path),
+        ["gen-rust", path] => generate_rust(path),
+        ["run", path, ticks] => run(path, ticks, "100"),
+        ["run", path, ticks, tick_duration] => run(path, ticks, tick_duration),
+        _ => exit_with_usage(),
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn run(path: &str, ticks: &str, tick_duration: &str) {
+    let (Ok(ticks), Ok(tick_duration)) = (ticks.parse::<u64>(), tick_duration.parse::<u64>())
+    else {
+        exit_with_usage();
+    };
+    let mut graph = init_map(path);
+    if let Some(keyboard) = graph.get_mut_keyboard(0) {
+        keyboard.read_from(io::stdin());
+    }
+    let tick_duration = Duration::from_millis(tick_duration);
+    run_displays(&mut graph, ticks, tick_duration, &mut io::stdout());
+}
+
+#[allow(clippy::bool_comparison)]
+fn example() {
     /*
      * A > c
      * B > c
@@ -0,0 +1,227 @@
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+
+type NodeId = u32;
+
+/// words read at the address given by the states of the address nodes, the memory outputs of the
+/// graph being the bits of the word at the current address
+/// a ROM is a memory loaded from a file, a RAM a memory with a write enable node, the states of
+/// the data in nodes being written at the current address when it turns on, and a disk a RAM
+/// whose words are kept in a file
+#[derive(Debug)]
+pub struct Memory {
+    pub words: Vec<u64>,
+    pub address: Vec<NodeId>, // the nodes giving the address, the least significant first
+    pub data_in: Vec<NodeId>, // the nodes giving the word to write, the least significant first
+    pub write_enable: Option<NodeId>,
+    pub file: Option<File>, // the file every written word is written to
+}
+
+impl Memory {
+    pub fn new(words: Vec<u64>, address: Vec<NodeId>) -> Memory {
+        assert!(
+            address.len() < usize::BITS as usize,
+            "a memory can't have more than {} address nodes",
+            usize::BITS - 1
+        );
+        Memory {
+            words,
+            address,
+            data_in: vec![],
+            write_enable: None,
+            file: None,
+        }
+    }
+
+    /// a RAM of `depth` words filled with 0, the size of the words is the number of data in nodes
+    pub fn new_ram(
+        depth: usize,
+        address: Vec<NodeId>,
+        data_in: Vec<NodeId>,
+        write_enable: NodeId,
+    ) -> Memory {
+        assert!(
+            data_in.len() <= u64::BITS as usize,
+            "the words of a memory have at most 64 bits"
+        );
+        let mut memory = Memory::new(vec![0; depth], address);
+        assert!(
+            depth <= 1 << memory.address.len(),
+            "the address nodes can't reach the {} words",
+            depth
+        );
+        memory.data_in = data_in;
+        memory.write_enable = Some(write_enable);
+        memory
+    }
+
+    /// load the words from a file, a .hex file holds one hexadecimal word per whitespace
+    /// separated token and any other file one word per byte
+    pub fn from_file(path: &str, address: Vec<NodeId>) -> Memory {
+        let is_hex = Path::new(path).extension().is_some_and(|ext| ext == "hex");
+        let words = if is_hex {
+            fs::read_to_string(path)
+                .unwrap_or_else(|_| panic!("Failed to read the file: {}", path))
+                .split_whitespace()
+                .map(|word| {
+                    u64::from_str_radix(word, 16)
+                        .unwrap_or_else(|_| panic!("{} is not a valid word:\n{}", path, word))
+                })
+                .collect()
+        } else {
+            fs::read(path)
+                .unwrap_or_else(|_| panic!("Failed to read the file: {}", path))
+                .into_iter()
+                .map(u64::from)
+                .collect()
+        };
+        Memory::new(words, address)
+    }
+
+    /// a RAM whose words are stored in the file, little endian on as many bytes as the data in
+    /// nodes need, so its contents are kept across runs
+    /// the file is created if it doesn't exist and extended with 0 up to the `depth` words
+    pub fn new_disk(
+        path: &str,
+        depth: usize,
+        address: Vec<NodeId>,
+        data_in: Vec<NodeId>,
+        write_enable: NodeId,
+    ) -> Memory {
+        let mut memory = Memory::new_ram(depth, address, data_in, write_enable);
+        let word_bytes = memory.get_word_bytes();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .unwrap_or_else(|_| panic!("Failed to open the file: {}", path));
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .unwrap_or_else(|_| panic!("Failed to read the file: {}", path));
+        if bytes.len() < depth * word_bytes {
+            bytes.resize(depth * word_bytes, 0);
+            file.set_len(bytes.len() as u64)
+                .unwrap_or_else(|_| panic!("Failed to extend the file: {}", path));
+        }
+        memory.words = bytes
+            .chunks(word_bytes)
+            .take(depth)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .rev()
+                    .fold(0, |word, byte| word << 8 | *byte as u64)
+            })
+            .collect();
+        memory.file = Some(file);
+        memory
+    }
+
+    /* getters */
+    /// the number of bytes a word takes in the file of a disk
+    pub fn get_word_bytes(&self) -> usize {
+        self.data_in.len().div_ceil(8).max(1)
+    }
+
+    /// the word at the address, 0 past the end of the memory
+    pub fn read(&self, address: usize) -> u64 {
+        self.words.get(address).copied().unwrap_or(0)
+    }
+
+    /// the words in the .hex format, one per line
+    pub fn to_hex(&self) -> String {
+        self.words
+            .iter()
+            .map(|word| format!("{:x}\n", word))
+            .collect()
+    }
+
+    /* pub methods */
+    /// write the word at the address and in the file of a disk, nothing happens past the end of
+    /// the memory
+    pub fn write(&mut self, address: usize, word: u64) {
+        let Some(old_word) = self.words.get_mut(address) else {
+            return;
+        };
+        *old_word = word;
+        let word_bytes = self.get_word_bytes();
+        if let Some(file) = &mut self.file {
+            file.seek(SeekFrom::Start((address * word_bytes) as u64))
+                .and_then(|_| file.write_all(&word.to_le_bytes()[..word_bytes]))
+                .expect("Failed to write the file of the disk");
+        }
+    }
+}
+
+/// a node having the state of one bit of the word at the current address of a memory of the
+/// graph
+#[derive(Debug)]
+pub struct MemoryOutput {
+    pub is_on: bool,
+    pub memory: usize, // index of the memory in the graph
+    pub bit: u32,
+    pub children: Vec<u32>,
+}
+
+impl MemoryOutput {
+    pub fn new(memory: usize, bit: u32, children: Vec<u32>) -> MemoryOutput {
+        assert!(bit < u64::BITS, "the words of a memory have 64 bits");
+        MemoryOutput {
+            is_on: false,
+            memory,
+            bit,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_file() {
+        let memory = Memory::from_file("./components/rom.hex", vec![1, 2]);
+        assert!(memory.words == [0, 1, 4, 9]);
+        assert!(memory.read(3) == 9);
+        assert!(memory.read(4) == 0);
+
+        let path = std::env::temp_dir().join("pomme_rom.bin");
+        fs::write(&path, [0x12, 0xff]).unwrap();
+        let memory = Memory::from_file(path.to_str().unwrap(), vec![]);
+        assert!(memory.words == [0x12, 0xff]);
+        assert!(memory.to_hex() == "12\nff\n");
+    }
+
+    #[test]
+    fn test_disk() {
+        let path = std::env::temp_dir().join("pomme_memory_disk.bin");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let data_in = (3..15).collect::<Vec<NodeId>>();
+        let mut disk = Memory::new_disk(path, 4, vec![1, 2], data_in.clone(), 15);
+        assert!(disk.get_word_bytes() == 2);
+        assert!(disk.words == [0; 4]);
+        disk.write(2, 0xabc);
+        assert!(fs::read(path).unwrap() == [0, 0, 0, 0, 0xbc, 0x0a, 0, 0]);
+
+        let disk = Memory::new_disk(path, 4, vec![1, 2], data_in, 15);
+        assert!(disk.words == [0, 0, 0xabc, 0]);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut memory = Memory::new_ram(3, vec![1, 2], vec![3], 4);
+        memory.write(1, 1);
+        memory.write(3, 1);
+        assert!(memory.words == [0, 1, 0]);
+    }
+}
@@ -1,10 +1,18 @@
+use crate::Clock;
+use crate::Input;
 use crate::LogicBlock;
+use crate::MemoryOutput;
+use crate::Random;
 use crate::StoringBlock;
 
 #[derive(Debug)]
 pub enum Node {
     LogicBlock(LogicBlock),
     StoringBlock(StoringBlock),
+    Clock(Clock),
+    Input(Input),
+    Memory(MemoryOutput),
+    Random(Random),
 }
 
 impl Node {
@@ -12,12 +20,30 @@ impl Node {
         match self {
             Node::LogicBlock(node) => node.is_on(),
             Node::StoringBlock(node) => node.is_on,
+            Node::Clock(node) => node.is_on,
+            Node::Input(node) => node.is_on,
+            Node::Memory(node) => node.is_on,
+            Node::Random(node) => node.is_on,
         }
     }
     pub fn get_children(&self) -> &Vec<u32> {
         match self {
             Node::LogicBlock(node) => &node.children,
             Node::StoringBlock(node) => &node.children,
+            Node::Clock(node) => &node.children,
+            Node::Input(node) => &node.children,
+            Node::Memory(node) => &node.children,
+            Node::Random(node) => &node.children,
+        }
+    }
+    pub fn get_mut_children(&mut self) -> &mut Vec<u32> {
+        match self {
+            Node::LogicBlock(node) => &mut node.children,
+            Node::StoringBlock(node) => &mut node.children,
+            Node::Clock(node) => &mut node.children,
+            Node::Input(node) => &mut node.children,
+            Node::Memory(node) => &mut node.children,
+            Node::Random(node) => &mut node.children,
         }
     }
 }
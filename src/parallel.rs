@@ -0,0 +1,510 @@
+use crate::Graph;
+use crate::Node;
+use crate::StoringBlock;
+use crate::Trigger;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Barrier;
+use std::thread;
+
+type NodeId = u32;
+
+// under this number of logic blocks per thread a level uses less threads
+const MIN_NODES_PER_THREAD: usize = 1024;
+
+/// a copy of a `Graph` evaluating the logic blocks level by level, the blocks of a level being
+/// split between several threads
+/// the storing blocks are updated between the evaluations until the graph is stable, so the
+/// states are the ones `apply_changes` gives: like there, an edge-triggered storing block takes
+/// the state its source has when its button changes, the state from before the changes when the
+/// source comes after the button
+/// the threads are started once per `evaluate` and wait for each other between the levels, the
+/// levels without any block whose parents changed are skipped
+/// the inputs are the input nodes, the logic blocks without parents (lamps and rocks) and the
+/// clocks
+#[derive(Debug)]
+pub struct ParallelGraph {
+    indexes: HashMap<NodeId, u32>,
+    // atomic so the threads write the blocks of a level while reading the previous levels
+    states: Vec<AtomicBool>,
+    requirements: Vec<u8>,
+    is_input: Vec<bool>,
+    // the parents of the node i are `parents[parents_offsets[i]..parents_offsets[i + 1]]`
+    parents_offsets: Vec<u32>,
+    parents: Vec<u32>,
+    // the children of the node i are `children[children_offsets[i]..children_offsets[i + 1]]`
+    children_offsets: Vec<u32>,
+    children: Vec<u32>,
+    levels: Vec<Vec<u32>>,
+    // the level of each logic block plus one, 0 for the nodes which aren't in a level
+    node_levels: Vec<u32>,
+    // the levels having a block whose parents changed since it was evaluated
+    dirty_levels: Vec<AtomicBool>,
+    // the storing blocks with their index, source and button node replaced by indexes, and
+    // whether they sample the state their source had before the pass (the edge-triggered blocks
+    // whose source isn't before their button in the levels)
+    storing_blocks: Vec<(u32, StoringBlock, bool)>,
+    nb_threads: usize,
+}
+
+impl ParallelGraph {
+    /// copy the current state of the graph, the levels are split between `nb_threads` threads
+    /// panics if the logic blocks make a loop, if one has more than 7 parents or if the graph has
+    /// forced nodes, memory outputs or random nodes
+    pub fn new(graph: &Graph, nb_threads: usize) -> Self {
+        assert!(nb_threads > 0, "at least one thread is needed");
+        assert!(
+            !graph.has_pending_actions(),
+            "can't convert a graph while changes are pending"
+        );
+        assert!(
+            !graph.has_forced_nodes(),
+            "can't convert a graph with forced nodes, they must be released"
+        );
+        let ids = graph.get_node_ids();
+        let indexes = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as u32))
+            .collect::<HashMap<NodeId, u32>>();
+
+        let mut states = Vec::with_capacity(ids.len());
+        let mut requirements = Vec::with_capacity(ids.len());
+        let mut is_input = Vec::with_capacity(ids.len());
+        let mut parents_offsets = Vec::with_capacity(ids.len() + 1);
+        let mut parents = Vec::new();
+        let mut children_offsets = Vec::with_capacity(ids.len() + 1);
+        let mut children = Vec::new();
+        let mut storing_blocks = Vec::new();
+        parents_offsets.push(0);
+        children_offsets.push(0);
+        for (index, id) in ids.iter().enumerate() {
+            let node = graph.get_node(*id).unwrap();
+            let node_parents = graph.get_parents(*id);
+            states.push(AtomicBool::new(node.is_on()));
+            match node {
+                Node::LogicBlock(node) => {
+                    // the value of a logic block is used as the shift of its u8 requirements
+                    assert!(
+                        node_parents.len() < 8,
+                        "the logic block {} has too many parents",
+                        id
+                    );
+                    requirements.push(node.get_requirements());
+                    is_input.push(node_parents.is_empty());
+                }
+                Node::StoringBlock(node) => {
+                    requirements.push(0);
+                    is_input.push(false);
+                    let mut storing_block = StoringBlock::new_with_trigger(
+                        node.is_on,
+                        indexes[&node.source],
+                        indexes[&node.button_node],
+                        vec![],
+                        node.trigger,
+                    );
+                    storing_block.was_button_on = node.was_button_on;
+                    storing_blocks.push((index as u32, storing_block, false));
+                }
+                Node::Clock(_) | Node::Input(_) => {
+                    requirements.push(0);
+                    is_input.push(true);
+                }
+                Node::Memory(_) => panic!("the memory output {} can't be converted", id),
+                Node::Random(_) => panic!("the random node {} can't be converted", id),
+            }
+            parents.extend(node_parents.iter().map(|parent| indexes[parent]));
+            parents_offsets.push(parents.len() as u32);
+            children.extend(node.get_children().iter().map(|child| indexes[child]));
+            children_offsets.push(children.len() as u32);
+        }
+
+        let levels = graph
+            .get_levels()
+            .expect("the logic blocks make a loop")
+            .into_iter()
+            .map(|level| level.iter().map(|node_id| indexes[node_id]).collect())
+            .collect::<Vec<Vec<u32>>>();
+        let mut node_levels = vec![0; ids.len()];
+        for (i, level) in levels.iter().enumerate() {
+            for index in level {
+                node_levels[*index as usize] = i as u32 + 1;
+            }
+        }
+        for (_, node, reads_previous_source) in &mut storing_blocks {
+            *reads_previous_source = node.trigger != Trigger::Level
+                && node_levels[node.source as usize] >= node_levels[node.button_node as usize];
+        }
+        // every level is evaluated the first time
+        let dirty_levels = levels.iter().map(|_| AtomicBool::new(true)).collect();
+        let max_level_len = levels.iter().map(|level| level.len()).max().unwrap_or(0);
+        let nb_threads = nb_threads.min(max_level_len.div_ceil(MIN_NODES_PER_THREAD).max(1));
+
+        ParallelGraph {
+            indexes,
+            states,
+            requirements,
+            is_input,
+            parents_offsets,
+            parents,
+            children_offsets,
+            children,
+            levels,
+            node_levels,
+            dirty_levels,
+            storing_blocks,
+            nb_threads,
+        }
+    }
+
+    /* getters */
+    pub fn is_on(&self, node_id: NodeId) -> bool {
+        self.states[self.get_index(node_id)].load(Ordering::Relaxed)
+    }
+
+    /* pub methods */
+    /// set the state of an input, the changes are applied by `evaluate`
+    pub fn set_input(&mut self, node_id: NodeId, is_on: bool) {
+        let index = self.get_index(node_id);
+        assert!(
+            self.is_input[index],
+            "the node {} is not an input, a logic block without parents nor a clock",
+            node_id
+        );
+        self.write_state(index as u32, is_on);
+    }
+
+    /// compute the state of every node from the inputs
+    /// panics if the storing blocks never become stable
+    pub fn evaluate(&mut self) {
+        // the current thread is the thread 0 and the only one updating the storing blocks
+        let mut storing_blocks = mem::take(&mut self.storing_blocks);
+        let mut previous_sources = self.get_sources(&storing_blocks);
+        let barrier = Barrier::new(self.nb_threads);
+        let is_done = AtomicBool::new(false);
+        let mut is_stable = false;
+        let graph = &*self;
+        thread::scope(|scope| {
+            for thread in 1..graph.nb_threads {
+                let (barrier, is_done) = (&barrier, &is_done);
+                scope.spawn(move || loop {
+                    graph.evaluate_levels(thread, barrier);
+                    // the thread 0 updates the storing blocks
+                    barrier.wait();
+                    barrier.wait();
+                    if is_done.load(Ordering::Relaxed) {
+                        return;
+                    }
+                });
+            }
+            let nb_passes = storing_blocks.len() + 1;
+            for pass in 0..nb_passes {
+                graph.evaluate_levels(0, &barrier);
+                barrier.wait();
+                is_stable = graph.update_storing_blocks(&mut storing_blocks, &mut previous_sources);
+                // the other threads only read if it is done after the next wait
+                if is_stable || pass + 1 == nb_passes {
+                    break;
+                }
+                barrier.wait();
+            }
+            is_done.store(true, Ordering::Relaxed);
+            barrier.wait();
+        });
+        self.storing_blocks = storing_blocks;
+        assert!(is_stable, "the storing blocks never become stable");
+    }
+
+    /* private methods */
+    fn get_index(&self, node_id: NodeId) -> usize {
+        *self.indexes.get(&node_id).expect("node not found") as usize
+    }
+
+    fn get_parents(&self, index: u32) -> &[u32] {
+        let start = self.parents_offsets[index as usize] as usize;
+        let end = self.parents_offsets[index as usize + 1] as usize;
+        &self.parents[start..end]
+    }
+
+    fn evaluate_logic_block(&self, index: u32) -> bool {
+        let value = self
+            .get_parents(index)
+            .iter()
+            .filter(|parent| self.states[**parent as usize].load(Ordering::Relaxed))
+            .count();
+        1 << value & self.requirements[index as usize] != 0
+    }
+
+    /// set the state of the node and mark the levels of its children if it changed
+    fn write_state(&self, index: u32, is_on: bool) {
+        if self.states[index as usize].swap(is_on, Ordering::Relaxed) == is_on {
+            return;
+        }
+        let start = self.children_offsets[index as usize] as usize;
+        let end = self.children_offsets[index as usize + 1] as usize;
+        for child in &self.children[start..end] {
+            let level = self.node_levels[*child as usize];
+            if level > 0 {
+                self.dirty_levels[level as usize - 1].store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// the states of the sources of the storing blocks
+    fn get_sources(&self, storing_blocks: &[(u32, StoringBlock, bool)]) -> Vec<bool> {
+        storing_blocks
+            .iter()
+            .map(|(_, node, _)| self.states[node.source as usize].load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// every storing block samples its source before any of them is written, so the storing
+    /// blocks being the source of each other all take the previous state
+    /// the states of the sources before the pass are replaced by the ones before the next pass
+    /// returns whether none of them changed
+    fn update_storing_blocks(
+        &self,
+        storing_blocks: &mut [(u32, StoringBlock, bool)],
+        previous_sources: &mut Vec<bool>,
+    ) -> bool {
+        let mut sampled_values = Vec::new();
+        for ((index, node, reads_previous_source), previous_source) in
+            storing_blocks.iter_mut().zip(previous_sources.iter())
+        {
+            let is_button_on = self.states[node.button_node as usize].load(Ordering::Relaxed);
+            let is_sampling = node.is_sampling(is_button_on);
+            node.was_button_on = is_button_on;
+            if is_sampling {
+                let is_source_on = match reads_previous_source {
+                    true => *previous_source,
+                    false => self.states[node.source as usize].load(Ordering::Relaxed),
+                };
+                sampled_values.push((*index, is_source_on));
+            }
+        }
+        let mut is_stable = true;
+        for (index, is_on) in sampled_values {
+            is_stable &= self.states[index as usize].load(Ordering::Relaxed) == is_on;
+            self.write_state(index, is_on);
+        }
+        *previous_sources = self.get_sources(storing_blocks);
+        is_stable
+    }
+
+    /// the blocks of a level only read the states of the previous levels so they are split
+    /// between the threads, which wait for each other before the next level
+    /// every thread skips the same levels, the dirty levels being only marked by the previous ones
+    fn evaluate_levels(&self, thread: usize, barrier: &Barrier) {
+        for (i, level) in self.levels.iter().enumerate() {
+            if !self.dirty_levels[i].load(Ordering::Relaxed) {
+                continue;
+            }
+            let nb_threads = self
+                .nb_threads
+                .min(level.len().div_ceil(MIN_NODES_PER_THREAD));
+            let chunk_size = level.len().div_ceil(nb_threads);
+            if let Some(chunk) = level.chunks(chunk_size).nth(thread) {
+                for index in chunk {
+                    self.write_state(*index, self.evaluate_logic_block(*index));
+                }
+            }
+            barrier.wait();
+            if thread == 0 {
+                self.dirty_levels[i].store(false, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_map;
+    use crate::Input;
+    use crate::LogicBlock;
+
+    fn assert_same_states(map: &Graph, parallel_map: &ParallelGraph) {
+        for node_id in map.get_node_ids() {
+            assert!(map.get_node(node_id).unwrap().is_on() == parallel_map.is_on(node_id));
+        }
+    }
+
+    #[test]
+    fn test_adder() {
+        let mut map = init_map("./components/adder.pc");
+        let mut parallel_map = ParallelGraph::new(&map, 4);
+        for (a, b) in [(96, 37), (255, 1), (0, 0), (170, 85), (13, 200)] {
+            for i in 0..8 {
+                for (node_id, is_on) in [(i + 1, a >> i & 1 == 1), (i + 9, b >> i & 1 == 1)] {
+                    if map.get_node(node_id).unwrap().is_on() == is_on {
+                        continue;
+                    }
+                    match is_on {
+                        true => map.turn_on_lamp(node_id),
+                        false => map.turn_off_lamp(node_id),
+                    }
+                    parallel_map.set_input(node_id, is_on);
+                }
+            }
+            map.apply_changes();
+            parallel_map.evaluate();
+            assert_same_states(&map, &parallel_map);
+        }
+    }
+
+    #[test]
+    fn test_saver() {
+        let mut map = init_map("./components/saver.pc");
+        let mut parallel_map = ParallelGraph::new(&map, 2);
+        for node_id in [6, 7, 9] {
+            map.turn_on_lamp(node_id);
+            parallel_map.set_input(node_id, true);
+        }
+        map.apply_changes();
+        parallel_map.evaluate();
+        assert_same_states(&map, &parallel_map);
+        for node_id in [6, 9] {
+            map.turn_off_lamp(node_id);
+            parallel_map.set_input(node_id, false);
+        }
+        map.apply_changes();
+        parallel_map.evaluate();
+        assert_same_states(&map, &parallel_map);
+        assert!(parallel_map.is_on(15));
+    }
+
+    #[test]
+    fn test_shift_register() {
+        let mut map = init_map("./components/shift.pc");
+        let mut parallel_map = ParallelGraph::new(&map, 2);
+        for is_on in [true, false, true] {
+            for (node_id, is_on) in [(1, is_on), (2, true), (2, false)] {
+                map.set(node_id, is_on).unwrap();
+                map.apply_changes();
+                parallel_map.set_input(node_id, is_on);
+                parallel_map.evaluate();
+            }
+        }
+        assert_same_states(&map, &parallel_map);
+        assert!(parallel_map.is_on(4) && !parallel_map.is_on(5) && parallel_map.is_on(6));
+    }
+
+    #[test]
+    fn test_wide_level() {
+        // enough inverters in a single level to use every thread
+        let nb_nodes = MIN_NODES_PER_THREAD as u32 * 4;
+        let mut nodes = vec![(
+            Node::LogicBlock(LogicBlock::new(0, (1..=nb_nodes).collect())),
+            0,
+        )];
+        for node_id in 1..=nb_nodes {
+            nodes.push((Node::LogicBlock(LogicBlock::new(0b00001, vec![])), node_id));
+        }
+        let mut map = Graph::new();
+        map.insert_nodes(nodes);
+        map.init_graph_state();
+        let mut parallel_map = ParallelGraph::new(&map, 4);
+        map.turn_on_lamp(0);
+        map.apply_changes();
+        parallel_map.set_input(0, true);
+        parallel_map.evaluate();
+        assert_same_states(&map, &parallel_map);
+        assert!(!parallel_map.is_on(nb_nodes));
+    }
+
+    #[test]
+    fn test_edge_triggered() {
+        // the node 3 samples its source before the input changes it and the node 6 after
+        let mut map = init_map("./components/edge.pc");
+        let mut parallel_map = ParallelGraph::new(&map, 2);
+        for is_on in [true, false, true] {
+            map.set(1, is_on).unwrap();
+            map.apply_changes();
+            parallel_map.set_input(1, is_on);
+            parallel_map.evaluate();
+            assert_same_states(&map, &parallel_map);
+        }
+        assert!(map.is_on(3) && map.is_on(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "never become stable")]
+    fn test_unstable_wide_level() {
+        // the threads stop even if the storing blocks never become stable
+        let nb_nodes = MIN_NODES_PER_THREAD as u32 * 4;
+        let mut nodes = vec![(
+            Node::LogicBlock(LogicBlock::new(0, (1..=nb_nodes).collect())),
+            0,
+        )];
+        for node_id in 1..=nb_nodes {
+            nodes.push((Node::LogicBlock(LogicBlock::new(0b00001, vec![])), node_id));
+        }
+        // the storing block follows its own inverted state while the input is on
+        let (input, storing_block, inverter) = (nb_nodes + 1, nb_nodes + 2, nb_nodes + 3);
+        nodes.push((Node::Input(Input::new(false, vec![])), input));
+        nodes.push((
+            Node::StoringBlock(StoringBlock::new(false, inverter, input, vec![inverter])),
+            storing_block,
+        ));
+        nodes.push((Node::LogicBlock(LogicBlock::new(0b00001, vec![])), inverter));
+        let mut map = Graph::new();
+        map.insert_nodes(nodes);
+        map.init_graph_state();
+        let mut parallel_map = ParallelGraph::new(&map, 4);
+        assert!(parallel_map.nb_threads == 4);
+        parallel_map.set_input(input, true);
+        parallel_map.evaluate();
+    }
+
+    #[test]
+    fn test_dirty_levels() {
+        let map = init_map("./components/adder.pc");
+        let mut parallel_map = ParallelGraph::new(&map, 2);
+        let get_dirty_levels = |parallel_map: &ParallelGraph| {
+            (0..parallel_map.levels.len())
+                .filter(|i| parallel_map.dirty_levels[*i].load(Ordering::Relaxed))
+                .collect::<Vec<usize>>()
+        };
+        parallel_map.evaluate();
+        assert!(get_dirty_levels(&parallel_map).is_empty());
+        // setting an input to its state changes nothing
+        parallel_map.set_input(1, parallel_map.is_on(1));
+        assert!(get_dirty_levels(&parallel_map).is_empty());
+        parallel_map.set_input(1, !parallel_map.is_on(1));
+        let mut levels = map
+            .get_node(1)
+            .unwrap()
+            .get_children()
+            .iter()
+            .map(|child| parallel_map.node_levels[parallel_map.get_index(*child)] as usize - 1)
+            .collect::<Vec<usize>>();
+        levels.sort();
+        levels.dedup();
+        assert!(get_dirty_levels(&parallel_map) == levels);
+        parallel_map.evaluate();
+        assert!(get_dirty_levels(&parallel_map).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "too many parents")]
+    fn test_too_many_parents() {
+        let mut map = Graph::new();
+        let mut nodes = (1..=8)
+            .map(|node_id| (Node::LogicBlock(LogicBlock::new(0b11111, vec![9])), node_id))
+            .collect::<Vec<(Node, NodeId)>>();
+        nodes.push((Node::LogicBlock(LogicBlock::new(0b00000, vec![])), 9));
+        map.insert_nodes(nodes);
+        ParallelGraph::new(&map, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "forced nodes")]
+    fn test_forced_nodes() {
+        let mut map = init_map("./components/adder.pc");
+        map.force(25, true);
+        map.apply_changes();
+        ParallelGraph::new(&map, 2);
+    }
+}
@@ -0,0 +1,79 @@
+type NodeId = u32;
+
+/// a node drawing a new pseudo-random state on each rising edge of its clock, or at each tick of
+/// `run_for` when it has none, the draws only depend on the seed of the graph and the id of the node
+/// so a run can be reproduced
+#[derive(Debug)]
+pub struct Random {
+    pub is_on: bool,
+    pub clock: Option<NodeId>,
+    pub state: u64, // the state of the generator (splitmix64)
+    // the state of the clock node when the node was last updated
+    pub was_clock_on: bool,
+    pub children: Vec<u32>,
+}
+
+impl Random {
+    pub fn new(clock: Option<NodeId>, children: Vec<u32>) -> Random {
+        Random {
+            is_on: false,
+            clock,
+            state: 0,
+            was_clock_on: false,
+            children,
+        }
+    }
+
+    /* pub methods */
+    /// start the generator from the seed of the graph, each node getting its own sequence
+    pub fn seed(&mut self, seed: u64, node_id: NodeId) {
+        self.state = mix(mix(seed) ^ node_id as u64);
+    }
+
+    /// draw the next state of the node
+    pub fn draw(&mut self) -> bool {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        self.is_on = mix(self.state) >> 63 == 1;
+        self.is_on
+    }
+}
+
+/// the output function of splitmix64
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ z >> 30).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ z >> 27).wrapping_mul(0x94d049bb133111eb);
+    z ^ z >> 31
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw() {
+        let mut random = Random::new(None, vec![]);
+        let bits = (0..64).map(|_| random.draw()).collect::<Vec<bool>>();
+        let nb_on = bits.iter().filter(|is_on| **is_on).count();
+        assert!(nb_on > 16 && nb_on < 48);
+
+        // the same state gives the same bits
+        let mut random = Random::new(None, vec![]);
+        assert!((0..64).map(|_| random.draw()).collect::<Vec<bool>>() == bits);
+        random.state = 1;
+        assert!((0..64).map(|_| random.draw()).collect::<Vec<bool>>() != bits);
+    }
+
+    #[test]
+    fn test_seed() {
+        let get_state = |seed, node_id| {
+            let mut random = Random::new(None, vec![]);
+            random.seed(seed, node_id);
+            random.state
+        };
+        // the seed and the id are mixed, not only combined bit by bit
+        assert!(get_state(0, 3) != get_state(1 << 32, 2));
+        assert!(get_state(0, 3) != get_state(1, 2));
+        assert!(get_state(7, 3) == get_state(7, 3));
+    }
+}
@@ -1,3 +1,26 @@
+/// when the storing block takes the value of its source
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// as long as the button node is on
+    Level,
+    /// when the button node turns on
+    RisingEdge,
+    /// when the button node turns off
+    FallingEdge,
+}
+
+/// a node keeping its state until its button node lets it take the state of its source
+/// the button and the source are always parents of the block, the graph adds the missing links
+/// when the block is inserted, so the block is updated each time one of them changes:
+/// - a level triggered block has the state of its source as long as its button is on and keeps
+///   its state while the button is off
+/// - an edge triggered block takes the state of its source when its button turns on (rising) or
+///   off (falling) and keeps it until the next edge, the edges of the initialisation don't count
+///
+/// when a change of the button comes with a change of the source (the source depending on the
+/// button), the block reads the source before the change of the button reaches anything else, so
+/// it takes the state the source had just before, the source must be the button itself to get its
+/// new state
 #[derive(Debug)]
 pub struct StoringBlock {
     pub is_on: bool,
@@ -5,6 +28,10 @@ pub struct StoringBlock {
     pub button_node: u32, // if and only if button_node is on the storing block take the value of
     // source
     pub children: Vec<u32>,
+    pub trigger: Trigger,
+    pub was_button_on: bool, // state of the button node the last time the block has been updated
+    // value of the source when the button node changed, taken the next time the block is updated
+    pub sampled_value: Option<bool>,
 }
 
 impl StoringBlock {
@@ -14,11 +41,53 @@ impl StoringBlock {
         }
     }
     pub fn new(is_on: bool, source: u32, button_node: u32, children: Vec<u32>) -> StoringBlock {
+        StoringBlock::new_with_trigger(is_on, source, button_node, children, Trigger::Level)
+    }
+    pub fn new_with_trigger(
+        is_on: bool,
+        source: u32,
+        button_node: u32,
+        children: Vec<u32>,
+        trigger: Trigger,
+    ) -> StoringBlock {
         StoringBlock {
             is_on,
             source,
             button_node,
             children,
+            trigger,
+            was_button_on: false,
+            sampled_value: None,
+        }
+    }
+
+    /// whether the block takes the value of its source when its button node is in the given
+    /// state, `was_button_on` must be updated after
+    pub fn is_sampling(&self, is_button_on: bool) -> bool {
+        match self.trigger {
+            Trigger::Level => is_button_on,
+            Trigger::RisingEdge => is_button_on && !self.was_button_on,
+            Trigger::FallingEdge => !is_button_on && self.was_button_on,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_sampling_test() {
+        let mut node = StoringBlock::new_with_trigger(false, 1, 2, vec![], Trigger::RisingEdge);
+        assert!(node.is_sampling(true));
+        assert!(!node.is_sampling(false));
+        node.was_button_on = true;
+        assert!(!node.is_sampling(true));
+
+        node.trigger = Trigger::FallingEdge;
+        assert!(node.is_sampling(false));
+        node.trigger = Trigger::Level;
+        assert!(node.is_sampling(true));
+        assert!(!node.is_sampling(false));
+    }
+}
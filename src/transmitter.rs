@@ -0,0 +1,107 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+type NodeId = u32;
+
+/// sends the byte given by the states of the data nodes every time the strobe turns on, the
+/// bytes are written to the writer or kept in `sent` when there is none
+/// the data nodes are read once the wave in which the strobe turns on is applied
+pub struct Transmitter {
+    pub data: Vec<NodeId>, // the least significant first
+    pub strobe: NodeId,
+    pub sent: Vec<u8>,
+    writer: Option<Box<dyn Write>>,
+    // the file created when the first byte is sent, then used as the writer
+    path: Option<PathBuf>,
+}
+
+impl fmt::Debug for Transmitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transmitter")
+            .field("data", &self.data)
+            .field("strobe", &self.strobe)
+            .field("sent", &self.sent)
+            .field("has_writer", &self.writer.is_some())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Transmitter {
+    pub fn new(data: Vec<NodeId>, strobe: NodeId) -> Transmitter {
+        assert!(data.len() <= 8, "a transmitter has at most 8 data nodes");
+        Transmitter {
+            data,
+            strobe,
+            sent: Vec::new(),
+            writer: None,
+            path: None,
+        }
+    }
+
+    /// a transmitter writing the bytes to the writer, like the standard output or a file
+    pub fn new_writer(
+        data: Vec<NodeId>,
+        strobe: NodeId,
+        writer: impl Write + 'static,
+    ) -> Transmitter {
+        let mut transmitter = Transmitter::new(data, strobe);
+        transmitter.writer = Some(Box::new(writer));
+        transmitter
+    }
+
+    /// a transmitter writing the bytes to the file, which is only created (or truncated) when the
+    /// first byte is sent
+    pub fn new_file(data: Vec<NodeId>, strobe: NodeId, path: PathBuf) -> Transmitter {
+        let mut transmitter = Transmitter::new(data, strobe);
+        transmitter.path = Some(path);
+        transmitter
+    }
+
+    /* pub methods */
+    pub fn send(&mut self, byte: u8) {
+        if let Some(path) = self.path.take() {
+            let file = File::create(&path)
+                .unwrap_or_else(|_| panic!("Failed to create the file: {}", path.display()));
+            self.writer = Some(Box::new(file));
+        }
+        match &mut self.writer {
+            Some(writer) => writer
+                .write_all(&[byte])
+                .and_then(|_| writer.flush())
+                .expect("Failed to write the byte sent by the transmitter"),
+            None => self.sent.push(byte),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_send() {
+        let mut transmitter = Transmitter::new(vec![1, 2], 3);
+        transmitter.send(b'o');
+        transmitter.send(b'k');
+        assert!(transmitter.sent == b"ok");
+
+        let path = std::env::temp_dir().join("pomme_transmitter.txt");
+        let file = File::create(&path).unwrap();
+        let mut transmitter = Transmitter::new_writer(vec![1, 2], 3, file);
+        transmitter.send(b'!');
+        assert!(transmitter.sent.is_empty());
+        assert!(fs::read(&path).unwrap() == b"!");
+
+        let path = std::env::temp_dir().join("pomme_transmitter_file.txt");
+        let _ = fs::remove_file(&path);
+        let mut transmitter = Transmitter::new_file(vec![1, 2], 3, path.clone());
+        assert!(!path.exists());
+        transmitter.send(b'o');
+        transmitter.send(b'k');
+        assert!(fs::read(&path).unwrap() == b"ok");
+    }
+}